@@ -16,7 +16,7 @@ fn main() -> Result<(), Box<Report>> {
         .with_logger(Logger::new().with_level(LogLevel::Error))
         .with_cache_directory(".cache");
 
-    let forest = Parser::new(&config).parse()?;
+    let forest = Parser::new(&config)?.parse()?;
 
     assert_eq!(forest.source.sources.len(), 3000);
     assert_eq!(forest.tree.trees.len(), 3000);
@@ -0,0 +1,60 @@
+//! A reference implementation of the remote cache protocol, storing entries in a directory.
+//!
+//! cargo run --example remote_cache_server -- 127.0.0.1:8080 /tmp/ara-forest-remote
+//! ARA_FOREST_REMOTE_CACHE=http://127.0.0.1:8080 ara-forest parse
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use tiny_http::Method;
+use tiny_http::Response;
+use tiny_http::Server;
+
+fn main() {
+    let mut arguments = env::args().skip(1);
+    let address = arguments
+        .next()
+        .unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let directory = PathBuf::from(
+        arguments
+            .next()
+            .unwrap_or_else(|| ".ara-forest-remote".to_string()),
+    );
+
+    fs::create_dir_all(&directory).unwrap();
+
+    let server = Server::http(&address).unwrap();
+    println!("serving {} on http://{address}", directory.display());
+
+    for mut request in server.incoming_requests() {
+        let key = request.url().trim_start_matches('/').to_string();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric()) {
+            let _ = request.respond(Response::empty(400));
+
+            continue;
+        }
+
+        let path = directory.join(key);
+        let response = match request.method() {
+            Method::Get => match fs::read(&path) {
+                Ok(data) => Response::from_data(data),
+                Err(_) => Response::from_data(vec![]).with_status_code(404),
+            },
+            Method::Put => {
+                let mut data = Vec::new();
+                match request
+                    .as_reader()
+                    .read_to_end(&mut data)
+                    .and_then(|_| fs::write(&path, data))
+                {
+                    Ok(()) => Response::from_data(vec![]).with_status_code(204),
+                    Err(_) => Response::from_data(vec![]).with_status_code(500),
+                }
+            }
+            _ => Response::from_data(vec![]).with_status_code(405),
+        };
+
+        let _ = request.respond(response);
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+
+use ara_parser::tree::Tree;
+use ara_reporting::Report;
+use ara_source::source::Source;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::stream::IndexedResult;
+use crate::tree::TreeBuilder;
+use crate::Forest;
+use crate::Parser;
+
+pub struct AsyncParser {
+    pub config: Arc<Config>,
+    handle: Option<Handle>,
+}
+
+impl AsyncParser {
+    pub fn new(config: Arc<Config>) -> Result<Self, Box<Report>> {
+        config.validate()?;
+
+        Ok(Self {
+            config,
+            handle: None,
+        })
+    }
+
+    /// Run the parsing work on the blocking pool of the given runtime,
+    /// instead of the runtime the parser is called from.
+    #[must_use]
+    pub fn with_handle(mut self, handle: Handle) -> Self {
+        self.handle = Some(handle);
+
+        self
+    }
+
+    /// Start parsing in the background, yielding each tree as soon as it is ready.
+    ///
+    /// Dropping, or cancelling, the returned stream stops the remaining work.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime, and no handle was provided.
+    pub fn stream(&self) -> TreeStream {
+        let handle = self.handle.clone().unwrap_or_else(Handle::current);
+        let config = self.config.clone();
+        let (sender, receiver) = mpsc::channel(config.threads.max(1) * 2);
+
+        handle.clone().spawn_blocking(move || {
+            let parser = Parser::unchecked(&config);
+            let files = match parser.prepare() {
+                Ok(files) => Arc::new(files),
+                Err(error) => {
+                    let _ = sender.blocking_send((0, Err(error)));
+
+                    return;
+                }
+            };
+
+            let cursor = Arc::new(AtomicUsize::new(0));
+            for _ in 0..parser.threads_count(files.len()) {
+                let config = config.clone();
+                let files = files.clone();
+                let cursor = cursor.clone();
+                let sender = sender.clone();
+
+                handle.spawn_blocking(move || {
+                    let mut tree_builder = TreeBuilder::new(&config);
+                    if config.symbol_index {
+                        tree_builder = tree_builder.with_symbol_cache();
+                    }
+
+                    tree_builder.build_all(
+                        &files,
+                        &cursor,
+                        TreeBuilder::build_indexed,
+                        |index, result| sender.blocking_send((index, result)).is_ok(),
+                    );
+                });
+            }
+        });
+
+        TreeStream { receiver }
+    }
+
+    pub async fn parse(&self) -> Result<Forest, Box<Report>> {
+        let mut stream = self.stream();
+
+        let mut results = Vec::new();
+        while let Some(result) = stream.receiver.recv().await {
+            results.push(result);
+        }
+
+        let mut forest = Forest::collect(results)?;
+        if self.config.symbol_index {
+            forest.index_symbols();
+        }
+
+        Ok(forest)
+    }
+}
+
+pub struct TreeStream {
+    receiver: mpsc::Receiver<IndexedResult>,
+}
+
+impl TreeStream {
+    pub async fn next(&mut self) -> Option<Result<(Source, Tree), Error>> {
+        self.receiver.recv().await.map(|(_, result)| result)
+    }
+
+    /// Stop parsing the remaining files.
+    ///
+    /// Trees that were already parsed can still be received from the stream.
+    pub fn cancel(&mut self) {
+        self.receiver.close();
+    }
+}
@@ -0,0 +1,476 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Instant;
+
+use clap::Parser as ClapParser;
+use clap::Subcommand;
+use clap::ValueEnum;
+use serde_json::json;
+
+use ara_forest::cache::Cache;
+use ara_forest::config::Config;
+use ara_forest::error::Error;
+use ara_forest::export::ExportFormat;
+use ara_forest::export::ReportExporter;
+use ara_forest::logger::LogLevel;
+use ara_forest::serializer::JsonSerializer;
+use ara_forest::source::SourceFilesCollector;
+use ara_forest::Parser;
+use ara_reporting::builder::ReportBuilder;
+use ara_reporting::issue::Issue;
+use ara_reporting::issue::IssueSeverity;
+use ara_reporting::Report;
+use ara_source::source::Source;
+use ara_source::source::SourceKind;
+use ara_source::SourceMap;
+
+#[derive(Debug, ClapParser)]
+#[command(name = "ara-forest", version, about)]
+struct Arguments {
+    /// The directory to search for the project manifest from, defaults to the current directory.
+    #[arg(long, global = true)]
+    root: Option<PathBuf>,
+
+    /// The number of threads to parse with.
+    #[arg(long, global = true)]
+    threads: Option<usize>,
+
+    /// The cache directory, relative to the project root.
+    #[arg(long, global = true)]
+    cache_dir: Option<String>,
+
+    /// The log level.
+    #[arg(long, global = true)]
+    log_level: Option<LogLevel>,
+
+    /// The output format.
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+    /// SARIF 2.1.0, only supported by the `parse` and `check` commands.
+    Sarif,
+    /// Checkstyle XML, only supported by the `parse` and `check` commands.
+    Checkstyle,
+    /// JUnit XML, only supported by the `parse` and `check` commands.
+    Junit,
+}
+
+impl Format {
+    fn export_format(&self) -> Option<ExportFormat> {
+        match self {
+            Format::Text | Format::Json => None,
+            Format::Sarif => Some(ExportFormat::Sarif),
+            Format::Checkstyle => Some(ExportFormat::Checkstyle),
+            Format::Junit => Some(ExportFormat::JUnit),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Parse the project, printing a summary and any diagnostics.
+    Parse,
+    /// Parse the project, exiting with a non-zero status if there are any errors.
+    Check,
+    /// List the files that would be parsed.
+    ListFiles,
+    /// Print the parsed tree of a single file as JSON.
+    Dump {
+        /// The file to dump, relative to the current directory.
+        file: PathBuf,
+    },
+    /// Manage the parse cache.
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum CacheCommand {
+    /// Print the number of entries in the cache, and their total size.
+    Stats,
+    /// Remove every entry from the cache.
+    Clear,
+    /// Remove the entries that are out of date, or don't belong to any source.
+    Prune,
+    /// Parse the project to refresh the cache, exiting with a non-zero status if there are any errors.
+    Warm,
+    /// Check the checksum of every entry, exiting with a non-zero status if any are corrupt.
+    Verify {
+        /// Remove the corrupt entries, rather than failing.
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Pack the up to date entries into a portable archive.
+    Export {
+        /// The archive file to create.
+        file: PathBuf,
+    },
+    /// Merge the entries of an archive into the cache, skipping out of date ones.
+    Import {
+        /// The archive file to import.
+        file: PathBuf,
+    },
+}
+
+fn main() -> ExitCode {
+    let arguments = Arguments::parse();
+
+    let config = match configure(&arguments) {
+        Ok(config) => config,
+        Err(report) => {
+            print_report(&arguments, None, &report);
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&arguments, &config) {
+        Ok(code) => code,
+        Err(report) => {
+            print_report(&arguments, Some(&config), &report);
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn configure(arguments: &Arguments) -> Result<Config, Box<Report>> {
+    let root = match &arguments.root {
+        Some(root) => root.clone(),
+        None => env::current_dir().map_err(Error::io("."))?,
+    };
+
+    let mut config = Config::discover_from(root).and_then(Config::with_env_overrides)?;
+
+    if let Some(threads) = arguments.threads {
+        config = config.with_threads(threads);
+    }
+
+    if let Some(cache_dir) = &arguments.cache_dir {
+        config = config.with_cache_directory(cache_dir);
+    }
+
+    if let Some(level) = arguments.log_level {
+        let logger = config.logger.take().unwrap_or_default();
+
+        config = config.with_logger(logger.with_level(level));
+    }
+
+    config.validate()?;
+
+    Ok(config)
+}
+
+fn run(arguments: &Arguments, config: &Config) -> Result<ExitCode, Box<Report>> {
+    if arguments.format.export_format().is_some()
+        && matches!(
+            arguments.command,
+            Command::ListFiles | Command::Cache { .. }
+        )
+    {
+        return Err(Box::new(Report::new().with_issue(Issue::from_string(
+            format!(
+                "the `{}` format is only supported by the `parse` and `check` commands.",
+                arguments.format.to_possible_value().unwrap().get_name()
+            ),
+        ))));
+    }
+
+    match &arguments.command {
+        Command::Parse => parse(arguments, config, false),
+        Command::Check => parse(arguments, config, true),
+        Command::ListFiles => list_files(arguments, config),
+        Command::Dump { file } => dump(config, file),
+        Command::Cache { command } => cache(arguments, config, command),
+    }
+}
+
+fn parse(arguments: &Arguments, config: &Config, check: bool) -> Result<ExitCode, Box<Report>> {
+    let start = Instant::now();
+    let result = Parser::new(config)?.parse();
+    let duration = start.elapsed();
+
+    let (forest, report) = match result {
+        Ok(forest) => (Some(forest), None),
+        Err(report) => (None, Some(report)),
+    };
+
+    let count = |kind: Option<SourceKind>| {
+        forest.as_ref().map(|forest| {
+            forest
+                .source
+                .sources
+                .iter()
+                .filter(|source| kind.is_none_or(|kind| source.kind == kind))
+                .count()
+        })
+    };
+
+    let failed = report
+        .as_ref()
+        .and_then(|report| report.severity())
+        .is_some_and(|severity| severity >= IssueSeverity::Error);
+
+    match arguments.format {
+        Format::Text => match &report {
+            Some(report) => print_report(arguments, Some(config), report),
+            None if !check => println!(
+                "parsed {} file(s), {} definition(s) and {} script(s), in {:.2?}.",
+                count(None).unwrap_or_default(),
+                count(Some(SourceKind::Definition)).unwrap_or_default(),
+                count(Some(SourceKind::Script)).unwrap_or_default(),
+                duration,
+            ),
+            None => {}
+        },
+        Format::Json => println!(
+            "{}",
+            json!({
+                "files": count(None),
+                "definitions": count(Some(SourceKind::Definition)),
+                "scripts": count(Some(SourceKind::Script)),
+                "duration_ms": duration.as_millis(),
+                "issues": report.as_ref().map_or(vec![], |report| {
+                    ReportExporter::new(&config.root, vec![]).diagnostics(report)
+                }),
+            })
+        ),
+        Format::Sarif | Format::Checkstyle | Format::Junit => {
+            let exporter = ReportExporter::from_config(config)?;
+            let report = report.unwrap_or_default();
+
+            print!(
+                "{}",
+                exporter.export(&report, arguments.format.export_format().unwrap())
+            );
+        }
+    }
+
+    if check && failed {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn list_files(arguments: &Arguments, config: &Config) -> Result<ExitCode, Box<Report>> {
+    let files = SourceFilesCollector::new(config)
+        .collect()?
+        .into_iter()
+        .map(|file| {
+            file.strip_prefix(&config.root)
+                .unwrap_or(&file)
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+
+    match arguments.format {
+        Format::Text => files.iter().for_each(|file| println!("{file}")),
+        _ => println!("{}", json!(files)),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn dump(config: &Config, file: &Path) -> Result<ExitCode, Box<Report>> {
+    let file = env::current_dir().map_err(Error::io("."))?.join(file);
+    let (_, tree) = Parser::new(config)?.parse_file(&file)?;
+
+    println!(
+        "{}",
+        JsonSerializer::new()
+            .with_pretty(true)
+            .tree_to_string(&tree)
+            .map_err(|error| Error::SerializeError(file, error.into()))?
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn cache(
+    arguments: &Arguments,
+    config: &Config,
+    command: &CacheCommand,
+) -> Result<ExitCode, Box<Report>> {
+    let directory = match &config.cache {
+        Some(directory) => directory,
+        None => {
+            return Err(Box::new(Report::new().with_issue(Issue::from_string(
+                "no cache directory is configured, use `--cache-dir`, or set `cache` in the project manifest.",
+            ))))
+        }
+    };
+
+    let cache = Cache::new(config);
+    let mut code = ExitCode::SUCCESS;
+    let (output, message) = match command {
+        CacheCommand::Stats => {
+            let stats = cache.stats()?;
+
+            (
+                json!(stats),
+                format!(
+                    "{} entries, {} bytes, in {}.",
+                    stats.entries,
+                    stats.size,
+                    directory.display()
+                ),
+            )
+        }
+        CacheCommand::Clear => {
+            let removed = cache.clear()?;
+
+            (
+                json!({ "removed": removed }),
+                format!("removed {removed} entries from {}.", directory.display()),
+            )
+        }
+        CacheCommand::Prune => {
+            let removed = cache.prune()?;
+
+            (
+                json!({ "removed": removed }),
+                format!(
+                    "removed {removed} stale entries from {}.",
+                    directory.display()
+                ),
+            )
+        }
+        CacheCommand::Warm => {
+            let (stats, report) = Parser::new(config)?.warm_cache()?;
+            if !report.issues.is_empty() {
+                code = ExitCode::FAILURE;
+                if arguments.format == Format::Text {
+                    print_report(arguments, Some(config), &report);
+                }
+            }
+
+            (
+                json!({
+                    "files": stats.files,
+                    "hits": stats.hits,
+                    "misses": stats.misses,
+                    "failures": stats.failures,
+                    "issues": ReportExporter::new(&config.root, vec![]).diagnostics(&report),
+                }),
+                format!(
+                    "warmed {} in {} file(s), {} were already cached, {} were parsed, and {} failed.",
+                    directory.display(),
+                    stats.files,
+                    stats.hits,
+                    stats.misses,
+                    stats.failures,
+                ),
+            )
+        }
+        CacheCommand::Verify { remove } => {
+            let stats = cache.verify(*remove)?;
+            if !stats.corrupt.is_empty() && !remove {
+                code = ExitCode::FAILURE;
+            }
+
+            let mut message = format!(
+                "{} of {} entries in {} are corrupt{}",
+                stats.corrupt.len(),
+                stats.entries,
+                directory.display(),
+                if *remove { ", and were removed." } else { "." }
+            );
+            for entry in &stats.corrupt {
+                message.push_str(&format!("\n  {}", entry.display()));
+            }
+
+            (json!(stats), message)
+        }
+        CacheCommand::Export { file } => {
+            let exported = cache.export(file)?;
+
+            (
+                json!({ "exported": exported }),
+                format!("exported {exported} entries to {}.", file.display()),
+            )
+        }
+        CacheCommand::Import { file } => {
+            let stats = cache.import(file)?;
+
+            (
+                json!(stats),
+                format!(
+                    "imported {} entries into {}, skipped {} out of date entries.",
+                    stats.imported,
+                    directory.display(),
+                    stats.skipped
+                ),
+            )
+        }
+    };
+
+    match arguments.format {
+        Format::Text => println!("{message}"),
+        _ => println!("{output}"),
+    }
+
+    Ok(code)
+}
+
+// a failed parse doesn't produce a forest, so the sources referenced by the
+// report are loaded again to be able to display them.
+fn load_sources(config: &Config, report: &Report) -> SourceMap {
+    let mut origins = report
+        .issues
+        .iter()
+        .filter_map(|issue| issue.source.as_ref().map(|(origin, _, _)| origin))
+        .collect::<Vec<_>>();
+    origins.sort();
+    origins.dedup();
+
+    SourceMap::new(
+        origins
+            .into_iter()
+            .filter_map(|origin| {
+                fs::read_to_string(config.root.join(origin))
+                    .ok()
+                    .map(|content| Source::new(SourceKind::Script, origin, content))
+            })
+            .collect(),
+    )
+}
+
+fn print_report(arguments: &Arguments, config: Option<&Config>, report: &Report) {
+    let exporter = match config {
+        Some(config) => ReportExporter::new(&config.root, vec![]),
+        None => ReportExporter::new(PathBuf::new(), vec![]),
+    };
+
+    match arguments.format {
+        Format::Text => {
+            let sources = config.map_or_else(
+                || SourceMap::new(vec![]),
+                |config| load_sources(config, report),
+            );
+
+            if ReportBuilder::new(&sources).eprint(report).is_err() {
+                eprint!("{report}");
+            }
+        }
+        Format::Json => println!("{}", json!({ "issues": exporter.diagnostics(report) })),
+        Format::Sarif | Format::Checkstyle | Format::Junit => print!(
+            "{}",
+            exporter.export(report, arguments.format.export_format().unwrap())
+        ),
+    }
+}
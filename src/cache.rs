@@ -0,0 +1,574 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use ara_source::source::Source;
+use bincode::config;
+use bincode::Decode;
+use bincode::Encode;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::checksum;
+use crate::config::CacheKey;
+use crate::config::Config;
+use crate::error::Error;
+use crate::source::SourceFilesCollector;
+use crate::tree::TreeBuilder;
+use crate::ARA_CACHED_SOURCE_EXTENSION;
+use crate::ARA_CACHED_SYMBOLS_EXTENSION;
+use crate::ARA_CACHE_INDEX_DIRECTORY;
+use crate::ARA_PARSER_VERSION;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub size: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct WarmStats {
+    /// The number of files that were processed.
+    pub files: usize,
+    /// The number of trees that were already cached.
+    pub hits: usize,
+    /// The number of trees that had to be parsed.
+    pub misses: usize,
+    /// The number of files that failed to parse.
+    pub failures: usize,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct VerifyStats {
+    pub entries: usize,
+    pub corrupt: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ImportStats {
+    pub imported: usize,
+    pub skipped: usize,
+}
+
+/// Maps the origin of a source to the key of its entry, when using content addressed keys,
+/// or metadata validation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub origin: String,
+    pub key: u64,
+    #[serde(default)]
+    pub signature: u64,
+    #[serde(default)]
+    pub stamp: Option<FileStamp>,
+}
+
+/// The metadata of a source file, used to tell whether it changed without hashing its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub modified_secs: u64,
+    pub modified_nanos: u32,
+    pub size: u64,
+    pub inode: u64,
+}
+
+// files modified this recently could still change within the same timestamp, so their
+// metadata isn't trusted, the same way git treats "racily clean" index entries.
+const FILE_STAMP_RACE_WINDOW: Duration = Duration::from_secs(2);
+
+const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Encode, Decode)]
+struct Archive {
+    format: u32,
+    parser: String,
+    entries: Vec<ArchiveEntry>,
+}
+
+#[derive(Encode, Decode)]
+struct ArchiveEntry {
+    origin: String,
+    signature: u64,
+    data: Vec<u8>,
+}
+
+pub struct Cache<'a> {
+    config: &'a Config,
+}
+
+impl<'a> Cache<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self { config }
+    }
+
+    pub fn entries(&self) -> Result<Vec<PathBuf>, Error> {
+        self.files(ARA_CACHED_SOURCE_EXTENSION)
+    }
+
+    fn files(&self, extension: &str) -> Result<Vec<PathBuf>, Error> {
+        let directory = match &self.config.cache {
+            Some(directory) if directory.is_dir() => directory,
+            _ => return Ok(Vec::new()),
+        };
+
+        let extension = format!(".{extension}");
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(directory).map_err(Error::io(directory))? {
+            let entry = entry.map_err(Error::io(directory))?;
+            if entry
+                .file_type()
+                .map_err(Error::io(entry.path()))?
+                .is_file()
+                && entry.file_name().to_string_lossy().ends_with(&extension)
+            {
+                entries.push(entry.path());
+            }
+        }
+
+        entries.sort();
+
+        Ok(entries)
+    }
+
+    pub fn stats(&self) -> Result<CacheStats, Error> {
+        let mut stats = CacheStats::default();
+        for entry in self.entries()? {
+            stats.entries += 1;
+            stats.size += fs::metadata(&entry).map_err(Error::io(&entry))?.len();
+        }
+
+        Ok(stats)
+    }
+
+    /// The index of the cache, sorted by origin.
+    pub fn index(&self) -> Result<Vec<IndexEntry>, Error> {
+        let directory = match self.index_directory() {
+            Some(directory) if directory.is_dir() => directory,
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut index = Vec::new();
+        for entry in fs::read_dir(&directory).map_err(Error::io(&directory))? {
+            let path = entry.map_err(Error::io(&directory))?.path();
+            if let Some(entry) = self.read_index_entry(&path)? {
+                index.push(entry);
+            }
+        }
+
+        index.sort_by(|a, b| a.origin.cmp(&b.origin));
+
+        Ok(index)
+    }
+
+    /// Check the checksum of every entry, removing the corrupt ones if `remove` is set.
+    pub fn verify(&self, remove: bool) -> Result<VerifyStats, Error> {
+        if remove {
+            self.check_writable()?;
+        }
+
+        let mut stats = VerifyStats::default();
+        for entry in self.entries()? {
+            stats.entries += 1;
+
+            let data = fs::read(&entry).map_err(Error::io(&entry))?;
+            let valid = checksum::open(&data)
+                .is_some_and(|payload| self.config.serializer.deserialize(payload).is_ok());
+            if !valid {
+                log::warn!("corrupt cache entry ({}).", entry.display());
+
+                if remove {
+                    fs::remove_file(&entry).map_err(Error::io(&entry))?;
+                }
+
+                stats.corrupt.push(entry);
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Remove every entry from the cache, returning the number of removed entries.
+    pub fn clear(&self) -> Result<usize, Error> {
+        self.check_writable()?;
+
+        let entries = self.entries()?;
+        for entry in entries
+            .iter()
+            .chain(&self.files(ARA_CACHED_SYMBOLS_EXTENSION)?)
+        {
+            fs::remove_file(entry).map_err(Error::io(entry))?;
+        }
+
+        if let Some(directory) = self
+            .index_directory()
+            .filter(|directory| directory.is_dir())
+        {
+            fs::remove_dir_all(&directory).map_err(Error::io(&directory))?;
+        }
+
+        Ok(entries.len())
+    }
+
+    /// Remove the entries that don't belong to any of the current sources, or are out of date,
+    /// returning the number of removed entries.
+    pub fn prune(&self) -> Result<usize, Error> {
+        self.check_writable()?;
+
+        if self.config.cache.is_none() {
+            return Ok(0);
+        }
+
+        let tree_builder = TreeBuilder::new(self.config);
+        let mut fresh = HashSet::new();
+        let mut indexed = HashSet::new();
+        for source_path in SourceFilesCollector::new(self.config).collect()? {
+            // a source that can't be read has no fresh entry, so its entry is pruned.
+            let source = match tree_builder.build_source(&source_path) {
+                Ok(source) => source,
+                Err(error) => {
+                    log::warn!("skipping source: {}", error);
+
+                    continue;
+                }
+            };
+            let signature = self.config.hasher.hash(&source.content);
+            let entry = self.entry_path(&source, signature);
+            if tree_builder
+                .get_from_cache(&source, signature, &entry)
+                .is_ok()
+            {
+                fresh.insert(entry);
+                fresh.insert(self.symbols_path(source.origin.as_ref().unwrap(), signature));
+                indexed.insert(self.index_entry_path(source.origin.as_ref().unwrap()));
+            }
+        }
+
+        if let Some(directory) = self
+            .index_directory()
+            .filter(|directory| directory.is_dir())
+        {
+            for entry in fs::read_dir(&directory).map_err(Error::io(&directory))? {
+                let path = entry.map_err(Error::io(&directory))?.path();
+                if !indexed.contains(&path) {
+                    fs::remove_file(&path).map_err(Error::io(&path))?;
+                }
+            }
+        }
+
+        for symbols in self.files(ARA_CACHED_SYMBOLS_EXTENSION)? {
+            if !fresh.contains(&symbols) {
+                fs::remove_file(&symbols).map_err(Error::io(&symbols))?;
+            }
+        }
+
+        let mut removed = 0;
+        for entry in self.entries()? {
+            if !fresh.contains(&entry) {
+                log::info!("pruning stale cache entry ({}).", entry.display());
+
+                fs::remove_file(&entry).map_err(Error::io(&entry))?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Pack the up to date entries of the current sources into a single archive file,
+    /// returning the number of exported entries.
+    ///
+    /// The archive records the origin, and signature, of each entry, so it can be checked
+    /// against the sources when imported on another machine.
+    pub fn export(&self, path: &Path) -> Result<usize, Error> {
+        let tree_builder = TreeBuilder::new(self.config);
+        let mut entries = Vec::new();
+        for source_path in SourceFilesCollector::new(self.config).collect()? {
+            let source = match tree_builder.build_source(&source_path) {
+                Ok(source) => source,
+                Err(error) => {
+                    log::warn!("skipping source: {}", error);
+
+                    continue;
+                }
+            };
+            let signature = self.config.hasher.hash(&source.content);
+            for entry in self.lookup_paths(&source, signature) {
+                if tree_builder
+                    .get_from_cache(&source, signature, &entry)
+                    .is_ok()
+                {
+                    entries.push(ArchiveEntry {
+                        origin: source.origin.clone().unwrap(),
+                        signature,
+                        data: fs::read(&entry).map_err(Error::io(&entry))?,
+                    });
+
+                    break;
+                }
+            }
+        }
+
+        let count = entries.len();
+        let archive = Archive {
+            format: ARCHIVE_FORMAT_VERSION,
+            parser: ARA_PARSER_VERSION.to_string(),
+            entries,
+        };
+
+        let data = bincode::encode_to_vec(&archive, config::standard())
+            .map_err(|error| Error::SerializeError(path.to_path_buf(), error.into()))?;
+        fs::write(path, data).map_err(Error::io(path))?;
+
+        Ok(count)
+    }
+
+    /// Merge the entries of an archive created by [`Cache::export`] into the cache directory.
+    ///
+    /// Entries are skipped if their source is missing, or its content no longer matches.
+    pub fn import(&self, path: &Path) -> Result<ImportStats, Error> {
+        self.check_writable()?;
+
+        let mut stats = ImportStats::default();
+        let directory = match &self.config.cache {
+            Some(directory) => directory,
+            None => return Ok(stats),
+        };
+
+        let data = fs::read(path).map_err(Error::io(path))?;
+        let (archive, _): (Archive, _) = bincode::decode_from_slice(&data, config::standard())
+            .map_err(|error| Error::DeserializeError(path.to_path_buf(), error.into()))?;
+
+        if archive.format != ARCHIVE_FORMAT_VERSION || archive.parser != ARA_PARSER_VERSION {
+            return Err(Error::DeserializeError(
+                path.to_path_buf(),
+                format!(
+                    "the archive was created for version {} of the parser, using format {}, expected version {}, using format {}",
+                    archive.parser, archive.format, ARA_PARSER_VERSION, ARCHIVE_FORMAT_VERSION,
+                )
+                .into(),
+            ));
+        }
+
+        fs::create_dir_all(directory).map_err(Error::io(directory))?;
+
+        let tree_builder = TreeBuilder::new(self.config);
+        for entry in archive.entries {
+            let fresh = tree_builder
+                .build_source(&self.config.root.join(&entry.origin))
+                .ok()
+                .filter(|source| self.config.hasher.hash(&source.content) == entry.signature)
+                .filter(|_| {
+                    checksum::open(&entry.data)
+                        .and_then(|payload| self.config.serializer.deserialize(payload).ok())
+                        .is_some_and(|signed_tree| signed_tree.signature == entry.signature)
+                });
+
+            let source = match fresh {
+                Some(source) => source,
+                None => {
+                    log::info!("skipping out of date archive entry ({}).", entry.origin);
+
+                    stats.skipped += 1;
+                    continue;
+                }
+            };
+
+            let cached_file_path = self.entry_path(&source, entry.signature);
+            write(&cached_file_path, &entry.data)?;
+            if self.config.cache_key == CacheKey::Content {
+                self.update_index(&source, entry.signature, None)?;
+            }
+
+            stats.imported += 1;
+        }
+
+        Ok(stats)
+    }
+
+    fn check_writable(&self) -> Result<(), Error> {
+        if self.config.cache_read_only {
+            return Err(Error::ConfigError(
+                "the cache is read-only, its entries can't be changed".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn entry_path(&self, source: &Source, signature: u64) -> PathBuf {
+        let cache_path = self.config.cache.as_ref().unwrap();
+        let origin = source.origin.as_ref().unwrap();
+
+        cache_path.join(self.entry_name(origin, signature, ARA_CACHED_SOURCE_EXTENSION))
+    }
+
+    /// The paths the entry of the given source is looked up at, the writable cache
+    /// directory first, followed by each of the read-only layers.
+    pub(crate) fn lookup_paths(&self, source: &Source, signature: u64) -> Vec<PathBuf> {
+        self.entry_lookup_paths(source.origin.as_ref().unwrap(), signature)
+    }
+
+    pub(crate) fn entry_lookup_paths(&self, origin: &str, signature: u64) -> Vec<PathBuf> {
+        self.lookup(origin, signature, ARA_CACHED_SOURCE_EXTENSION)
+    }
+
+    /// The path the symbols of the given source are cached at, next to its entry.
+    pub(crate) fn symbols_path(&self, origin: &str, signature: u64) -> PathBuf {
+        let cache_path = self.config.cache.as_ref().unwrap();
+
+        cache_path.join(self.entry_name(origin, signature, ARA_CACHED_SYMBOLS_EXTENSION))
+    }
+
+    pub(crate) fn symbols_lookup_paths(&self, origin: &str, signature: u64) -> Vec<PathBuf> {
+        self.lookup(origin, signature, ARA_CACHED_SYMBOLS_EXTENSION)
+    }
+
+    fn lookup(&self, origin: &str, signature: u64, extension: &str) -> Vec<PathBuf> {
+        let name = self.entry_name(origin, signature, extension);
+
+        self.config
+            .cache
+            .iter()
+            .chain(&self.config.cache_layers)
+            .map(|directory| directory.join(&name))
+            .collect()
+    }
+
+    /// Record the key of the given source in the index, unless it is already up to date.
+    pub(crate) fn update_index(
+        &self,
+        source: &Source,
+        signature: u64,
+        stamp: Option<FileStamp>,
+    ) -> Result<(), Error> {
+        let path = self.index_entry_path(source.origin.as_ref().unwrap());
+        let entry = IndexEntry {
+            origin: source.origin.clone().unwrap(),
+            key: self.key(source.origin.as_ref().unwrap(), signature),
+            signature,
+            stamp,
+        };
+
+        if self.read_index_entry(&path)?.as_ref() == Some(&entry) {
+            return Ok(());
+        }
+
+        let directory = self.index_directory().unwrap();
+        fs::create_dir_all(&directory).map_err(Error::io(&directory))?;
+
+        let content = serde_json::to_vec(&entry)
+            .map_err(|error| Error::SerializeError(path.clone(), error.into()))?;
+
+        write(&path, &content)
+    }
+
+    pub(crate) fn read_index(&self, origin: &str) -> Result<Option<IndexEntry>, Error> {
+        if self.config.cache.is_none() {
+            return Ok(None);
+        }
+
+        self.read_index_entry(&self.index_entry_path(origin))
+    }
+
+    fn read_index_entry(&self, path: &PathBuf) -> Result<Option<IndexEntry>, Error> {
+        let content = match fs::read(path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(Error::IoError(path.clone(), error)),
+        };
+
+        serde_json::from_slice(&content)
+            .map(Some)
+            .map_err(|error| Error::DeserializeError(path.clone(), error.into()))
+    }
+
+    fn index_directory(&self) -> Option<PathBuf> {
+        self.config
+            .cache
+            .as_ref()
+            .map(|directory| directory.join(ARA_CACHE_INDEX_DIRECTORY))
+    }
+
+    fn index_entry_path(&self, origin: &str) -> PathBuf {
+        let origin = self.config.hasher.hash(origin);
+
+        self.index_directory()
+            .unwrap()
+            .join(origin.to_string())
+            .with_extension("json")
+    }
+
+    fn key(&self, origin: &str, signature: u64) -> u64 {
+        match self.config.cache_key {
+            CacheKey::Origin => self.config.hasher.hash(origin),
+            CacheKey::Content => self.content_key(signature),
+        }
+    }
+
+    pub(crate) fn content_key(&self, signature: u64) -> u64 {
+        self.config
+            .hasher
+            .hash(&format!("{}:{}", ARA_PARSER_VERSION, signature))
+    }
+
+    fn entry_name(&self, origin: &str, signature: u64, extension: &str) -> PathBuf {
+        PathBuf::from(self.key(origin, signature).to_string()).with_extension(extension)
+    }
+}
+
+/// Write the given data next to the given path, and move it into place, so a concurrent
+/// reader never sees a partially written file, e.g. when sources with the same content
+/// share an entry.
+pub(crate) fn write(path: &Path, data: &[u8]) -> Result<(), Error> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temporary = path.with_file_name(name);
+
+    fs::write(&temporary, data)
+        .and_then(|_| fs::rename(&temporary, path))
+        .map_err(|error| {
+            fs::remove_file(&temporary).ok();
+
+            Error::IoError(path.to_path_buf(), error)
+        })
+}
+
+impl FileStamp {
+    /// The stamp of the given file, or `None` if its metadata can't be trusted.
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?;
+        if SystemTime::now()
+            .duration_since(modified)
+            .map_or(true, |age| age < FILE_STAMP_RACE_WINDOW)
+        {
+            return None;
+        }
+
+        let modified = modified.duration_since(UNIX_EPOCH).ok()?;
+
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(&metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+
+        Some(Self {
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+            size: metadata.len(),
+            inode,
+        })
+    }
+}
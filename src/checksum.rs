@@ -0,0 +1,24 @@
+const MAGIC: &[u8; 4] = b"ARAC";
+const HEADER_LENGTH: usize = 8;
+
+/// Wrap the given payload in an envelope holding its checksum.
+pub(crate) fn seal(payload: Vec<u8>) -> Vec<u8> {
+    let mut data = Vec::with_capacity(HEADER_LENGTH + payload.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    data.extend(payload);
+
+    data
+}
+
+/// Unwrap the payload of the given envelope, returning `None` if it is corrupt.
+pub(crate) fn open(data: &[u8]) -> Option<&[u8]> {
+    if data.len() < HEADER_LENGTH || &data[..4] != MAGIC {
+        return None;
+    }
+
+    let checksum = u32::from_le_bytes(data[4..HEADER_LENGTH].try_into().unwrap());
+    let payload = &data[HEADER_LENGTH..];
+
+    (crc32fast::hash(payload) == checksum).then_some(payload)
+}
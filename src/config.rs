@@ -1,20 +1,89 @@
+use std::env;
+use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use ara_reporting::issue::Issue;
+use ara_reporting::Report;
+use ara_reporting::ReportFooter;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::executor::Executor;
+use crate::executor::ThreadExecutor;
 use crate::hash::ContentHasher;
 use crate::hash::FxHasher;
 use crate::logger::Logger;
+use crate::manifest::Manifest;
+use crate::manifest::ARA_MANIFEST_FILES;
+#[cfg(feature = "remote")]
+use crate::remote::HttpRemoteCache;
+use crate::remote::RemoteCache;
 use crate::serializer::BincodeSerializer;
 use crate::serializer::Serializer;
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LimitPolicy {
+    /// Skip sources that exceed a limit, logging a warning.
+    Skip,
+    /// Report sources that exceed a limit as issues.
+    Report,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Fail the parse on any cache problem.
+    Strict,
+    /// Log a warning, and carry on without the cache.
+    Warn,
+    /// Carry on without the cache.
+    Silent,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheKey {
+    /// Key cache entries by the origin of their source.
+    Origin,
+    /// Key cache entries by the content of their source, and the parser version.
+    ///
+    /// Entries survive moving files, or the project root, and identical sources
+    /// share a single entry.
+    Content,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheValidation {
+    /// Hash the content of every source to check its cache entry is up to date.
+    Content,
+    /// Trust the cache entry of a source whose modification time, size, and inode
+    /// didn't change since it was cached, without hashing its content.
+    Metadata,
+}
+
 pub struct Config {
     pub root: PathBuf,
     pub source: PathBuf,
     pub definitions: Vec<PathBuf>,
+    pub excludes: Vec<PathBuf>,
+    pub vendor: Option<PathBuf>,
     pub cache: Option<PathBuf>,
+    pub cache_policy: CachePolicy,
+    pub cache_read_only: bool,
+    pub cache_layers: Vec<PathBuf>,
+    pub cache_key: CacheKey,
+    pub cache_validation: CacheValidation,
+    pub remote_cache: Option<Box<dyn RemoteCache>>,
+    pub symbol_index: bool,
     pub threads: usize,
     pub logger: Option<Logger>,
     pub hasher: Box<dyn ContentHasher>,
     pub serializer: Box<dyn Serializer>,
+    pub executor: Box<dyn Executor>,
+    pub max_file_size: Option<u64>,
+    pub parse_timeout: Option<Duration>,
+    pub limit_policy: LimitPolicy,
 }
 
 impl Config {
@@ -23,12 +92,292 @@ impl Config {
             root: PathBuf::from(root.into()),
             source: PathBuf::from(String::default()),
             definitions: Vec::new(),
+            excludes: Vec::new(),
+            vendor: None,
             cache: None,
+            cache_policy: CachePolicy::Warn,
+            cache_read_only: false,
+            cache_layers: Vec::new(),
+            cache_key: CacheKey::Origin,
+            cache_validation: CacheValidation::Content,
+            remote_cache: None,
+            symbol_index: false,
             threads: num_cpus::get(),
             logger: None,
             hasher: Box::new(FxHasher::new()),
             serializer: Box::new(BincodeSerializer::new()),
+            executor: Box::new(ThreadExecutor::new()),
+            max_file_size: None,
+            parse_timeout: None,
+            limit_policy: LimitPolicy::Report,
+        }
+    }
+
+    /// Load the configuration from the given manifest file.
+    ///
+    /// The directory containing the manifest is used as the project root.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let manifest = Manifest::from_file(path)?;
+        let root = path.parent().unwrap_or_else(|| Path::new(""));
+
+        Ok(manifest.configure(Self::new(root.to_string_lossy())))
+    }
+
+    /// Load the configuration from the manifest in the given project root directory.
+    pub fn discover<P: AsRef<Path>>(root: P) -> Result<Self, Error> {
+        let root = root.as_ref();
+
+        ARA_MANIFEST_FILES
+            .iter()
+            .map(|name| root.join(name))
+            .find(|path| path.is_file())
+            .map(Self::from_file)
+            .unwrap_or_else(|| Err(Error::ManifestNotFound(root.to_path_buf())))
+    }
+
+    /// Load the configuration of the project containing the given directory, or file.
+    ///
+    /// The given path, and each of its parents, are searched for a manifest, the
+    /// first directory containing one is used as the project root.
+    pub fn discover_from<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let path = if path.is_relative() {
+            env::current_dir().map_err(Error::io(path))?.join(path)
+        } else {
+            path.to_path_buf()
+        };
+
+        path.ancestors()
+            .find_map(|directory| {
+                ARA_MANIFEST_FILES
+                    .iter()
+                    .map(|name| directory.join(name))
+                    .find(|manifest| manifest.is_file())
+            })
+            .map(Self::from_file)
+            .unwrap_or_else(|| Err(Error::ProjectNotFound(path)))
+    }
+
+    /// Override the configuration using the `ARA_FOREST_*` environment variables.
+    ///
+    /// To get the expected precedence, of defaults, then the manifest, then the
+    /// environment, and then explicit builders, call this after loading the
+    /// manifest, and before any other builder:
+    ///
+    /// ```no_run
+    /// use ara_forest::config::Config;
+    ///
+    /// let config = Config::discover("/path/to/project")?
+    ///     .with_env_overrides()?
+    ///     .with_threads(4);
+    /// # Ok::<(), ara_forest::error::Error>(())
+    /// ```
+    ///
+    /// The supported variables are:
+    ///
+    /// - `ARA_FOREST_THREADS`: the number of threads.
+    /// - `ARA_FOREST_CACHE_DIR`: the cache directory.
+    /// - `ARA_FOREST_CACHE_READ_ONLY`: whether the cache is read-only, `true` or `false`.
+    /// - `ARA_FOREST_CACHE_VALIDATION`: how cache entries are validated, `content` or `metadata`.
+    /// - `ARA_FOREST_REMOTE_CACHE`: the url of a remote HTTP cache, requires the `remote` feature.
+    /// - `ARA_FOREST_LOG`: the log level.
+    /// - `ARA_FOREST_LOG_FILE`: the log file.
+    /// - `ARA_FOREST_MAX_FILE_SIZE`: the maximum file size, in bytes.
+    pub fn with_env_overrides(self) -> Result<Self, Error> {
+        let mut variables = Vec::new();
+        // only the values of our own variables have to be valid unicode.
+        for (name, value) in env::vars_os() {
+            let name = match name.into_string() {
+                Ok(name) if name.starts_with("ARA_FOREST_") => name,
+                _ => continue,
+            };
+
+            match value.into_string() {
+                Ok(value) => variables.push((name, value)),
+                Err(_) => {
+                    return Err(Error::EnvironmentError(
+                        name,
+                        "expected a valid unicode value".to_string(),
+                    ))
+                }
+            }
+        }
+
+        self.with_overrides(variables)
+    }
+
+    /// Override the configuration using the given `ARA_FOREST_*` variables.
+    ///
+    /// See [`Config::with_env_overrides`] for the supported variables.
+    pub fn with_overrides<I, K, V>(mut self, variables: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (name, value) in variables {
+            let (name, value) = (name.as_ref(), value.as_ref());
+            let invalid = |message: String| Error::EnvironmentError(name.to_string(), message);
+
+            self = match name {
+                "ARA_FOREST_THREADS" => self.with_threads(
+                    value
+                        .parse()
+                        .map_err(|error| invalid(format!("invalid number of threads: {error}")))?,
+                ),
+                "ARA_FOREST_CACHE_DIR" => self.with_cache_directory(value),
+                "ARA_FOREST_CACHE_READ_ONLY" => match value {
+                    "1" | "true" => self.with_read_only_cache(true),
+                    "0" | "false" => self.with_read_only_cache(false),
+                    _ => return Err(invalid(format!("expected a boolean, found `{value}`"))),
+                },
+                "ARA_FOREST_CACHE_VALIDATION" => match value {
+                    "content" => self.with_cache_validation(CacheValidation::Content),
+                    "metadata" => self.with_cache_validation(CacheValidation::Metadata),
+                    _ => {
+                        return Err(invalid(format!(
+                            "expected `content` or `metadata`, found `{value}`"
+                        )))
+                    }
+                },
+                #[cfg(feature = "remote")]
+                "ARA_FOREST_REMOTE_CACHE" => self.with_remote_cache(HttpRemoteCache::new(value)),
+                #[cfg(not(feature = "remote"))]
+                "ARA_FOREST_REMOTE_CACHE" => {
+                    return Err(invalid(
+                        "a remote cache requires the `remote` feature".to_string(),
+                    ))
+                }
+                "ARA_FOREST_LOG" => {
+                    let level = value.parse().map_err(invalid)?;
+                    let logger = self.logger.take().unwrap_or_default();
+
+                    self.with_logger(logger.with_level(level))
+                }
+                "ARA_FOREST_LOG_FILE" => {
+                    let file = self.root.join(value);
+                    let logger = self.logger.take().unwrap_or_default();
+
+                    self.with_logger(logger.with_file(file))
+                }
+                "ARA_FOREST_MAX_FILE_SIZE" => self.with_max_file_size(
+                    value
+                        .parse()
+                        .map_err(|error| invalid(format!("invalid file size: {error}")))?,
+                ),
+                _ => self,
+            };
+        }
+
+        Ok(self)
+    }
+
+    /// Check the configuration for mistakes, reporting all of them at once.
+    pub fn validate(&self) -> Result<(), Box<Report>> {
+        let mut issues = Vec::new();
+
+        if !self.root.is_dir() {
+            issues.push(Issue::from_string(format!(
+                "project root {} does not exist, or is not a directory.",
+                self.root.display(),
+            )));
+        }
+
+        if self.threads == 0 {
+            issues.push(Issue::from_string(
+                "the number of threads must be greater than zero.",
+            ));
+        }
+
+        let source = self.root.join(&self.source);
+        if !source.is_dir() {
+            issues.push(Issue::from_string(format!(
+                "source {} does not exist, or is not a directory.",
+                source.display(),
+            )));
+        }
+
+        let definitions = self
+            .definitions
+            .iter()
+            .map(|definition| self.root.join(definition))
+            .collect::<Vec<_>>();
+        for (index, definition) in definitions.iter().enumerate() {
+            if !definition.is_dir() {
+                issues.push(Issue::from_string(format!(
+                    "definitions {} does not exist, or is not a directory.",
+                    definition.display(),
+                )));
+            }
+
+            if definition.starts_with(&source) || source.starts_with(definition) {
+                issues.push(Issue::from_string(format!(
+                    "definitions {} overlap with the source {}, files would be parsed twice.",
+                    definition.display(),
+                    source.display(),
+                )));
+            }
+
+            for other in &definitions[..index] {
+                if definition.starts_with(other) || other.starts_with(definition) {
+                    issues.push(Issue::from_string(format!(
+                        "definitions {} overlap with the definitions {}, files would be parsed twice.",
+                        definition.display(),
+                        other.display(),
+                    )));
+                }
+            }
+        }
+
+        if let Some(vendor) = &self.vendor {
+            let vendor = self.root.join(vendor);
+            if !vendor.is_dir() {
+                issues.push(Issue::from_string(format!(
+                    "vendor {} does not exist, or is not a directory.",
+                    vendor.display(),
+                )));
+            }
+        }
+
+        if let Some(cache) = &self.cache {
+            for directory in std::iter::once(&source).chain(&definitions) {
+                if cache.starts_with(directory) {
+                    issues.push(Issue::from_string(format!(
+                        "cache directory {} must not be inside of {}.",
+                        cache.display(),
+                        directory.display(),
+                    )));
+                }
+            }
+
+            if cache.exists() && !cache.is_dir() {
+                issues.push(Issue::from_string(format!(
+                    "cache directory {} is not a directory.",
+                    cache.display(),
+                )));
+            }
         }
+
+        for layer in &self.cache_layers {
+            if !layer.is_dir() {
+                issues.push(Issue::from_string(format!(
+                    "cache layer {} does not exist, or is not a directory.",
+                    layer.display(),
+                )));
+            }
+        }
+
+        if issues.is_empty() {
+            return Ok(());
+        }
+
+        Err(Box::new(Report {
+            issues,
+            footer: Some(ReportFooter::new(
+                "invalid configuration due to the above issue(s)",
+            )),
+        }))
     }
 
     #[must_use]
@@ -48,6 +397,24 @@ impl Config {
         self
     }
 
+    #[must_use]
+    pub fn with_excludes<E: Into<String>>(mut self, excludes: Vec<E>) -> Self {
+        self.excludes = excludes
+            .into_iter()
+            .map(|exclude| PathBuf::from(exclude.into()))
+            .collect();
+
+        self
+    }
+
+    /// Discover the definitions of the packages installed in the given vendor directory.
+    #[must_use]
+    pub fn with_vendor_directory<V: Into<String>>(mut self, vendor: V) -> Self {
+        self.vendor = Some(PathBuf::from(vendor.into()));
+
+        self
+    }
+
     #[must_use]
     pub fn with_cache_directory<C: Into<String>>(mut self, cache_dir: C) -> Self {
         let path = PathBuf::from(cache_dir.into());
@@ -61,6 +428,72 @@ impl Config {
         self
     }
 
+    #[must_use]
+    pub fn with_cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policy = policy;
+
+        self
+    }
+
+    /// Load trees from the cache, without ever writing to it.
+    #[must_use]
+    pub fn with_read_only_cache(mut self, read_only: bool) -> Self {
+        self.cache_read_only = read_only;
+
+        self
+    }
+
+    /// Read-only cache directories, looked up in order after the cache directory.
+    ///
+    /// Trees are never written to the layers, only to the cache directory, making it
+    /// possible to share a pre-built cache, while keeping local changes in an overlay.
+    #[must_use]
+    pub fn with_cache_layers<C: Into<String>>(mut self, layers: Vec<C>) -> Self {
+        self.cache_layers = layers
+            .into_iter()
+            .map(|layer| self.root.join(layer.into()))
+            .collect();
+
+        self
+    }
+
+    #[must_use]
+    pub fn with_cache_key(mut self, key: CacheKey) -> Self {
+        self.cache_key = key;
+
+        self
+    }
+
+    /// Trust the cache entries of sources whose file metadata didn't change, instead of
+    /// hashing their content, see [`CacheValidation::Metadata`].
+    ///
+    /// The metadata is recorded in the cache index, sources modified within the last couple
+    /// of seconds are always hashed, as they could still change without updating it.
+    #[must_use]
+    pub fn with_cache_validation(mut self, validation: CacheValidation) -> Self {
+        self.cache_validation = validation;
+
+        self
+    }
+
+    #[must_use]
+    pub fn with_remote_cache<R: RemoteCache + 'static>(mut self, remote_cache: R) -> Self {
+        self.remote_cache = Some(Box::new(remote_cache));
+
+        self
+    }
+
+    /// Build a [`crate::symbol::SymbolIndex`] of the forest while parsing, caching the
+    /// symbols of each source next to its tree.
+    ///
+    /// Streaming, and warming the cache, only cache the symbols, without indexing them.
+    #[must_use]
+    pub fn with_symbol_index(mut self, symbol_index: bool) -> Self {
+        self.symbol_index = symbol_index;
+
+        self
+    }
+
     #[must_use]
     pub fn with_threads(mut self, threads: usize) -> Self {
         self.threads = threads;
@@ -68,6 +501,46 @@ impl Config {
         self
     }
 
+    #[must_use]
+    pub fn with_executor<E: Executor + 'static>(mut self, executor: E) -> Self {
+        self.executor = Box::new(executor);
+
+        self
+    }
+
+    #[must_use]
+    pub fn with_serializer<S: Serializer + 'static>(mut self, serializer: S) -> Self {
+        self.serializer = Box::new(serializer);
+
+        self
+    }
+
+    #[must_use]
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+
+        self
+    }
+
+    /// Give up on sources that take longer than the given duration to parse.
+    ///
+    /// The parser can't be interrupted, so each worker parses on a helper thread that is
+    /// abandoned when a source times out, it keeps using a cpu until that source is parsed,
+    /// and a new helper thread is started for the next source of the worker.
+    #[must_use]
+    pub fn with_parse_timeout(mut self, timeout: Duration) -> Self {
+        self.parse_timeout = Some(timeout);
+
+        self
+    }
+
+    #[must_use]
+    pub fn with_limit_policy(mut self, policy: LimitPolicy) -> Self {
+        self.limit_policy = policy;
+
+        self
+    }
+
     #[must_use]
     pub fn with_logger(mut self, logger: Logger) -> Self {
         self.logger = Some(logger);
@@ -1,44 +1,108 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
 use ara_reporting::issue::Issue;
+use ara_reporting::issue::IssueSeverity;
 use ara_reporting::Report;
 
+pub type BoxedError = Box<dyn std::error::Error + Send + Sync>;
+
 #[derive(Debug)]
 pub enum Error {
-    CacheMiss,
-    SerializeError(String),
-    DeserializeError(String),
-    InvalidPath(String),
-    IoError(std::io::Error),
+    CacheMiss(String),
+    SerializeError(PathBuf, BoxedError),
+    DeserializeError(PathBuf, BoxedError),
+    InvalidPath(PathBuf),
+    IoError(PathBuf, std::io::Error),
     ParseError(Box<Report>),
     LogError(log::SetLoggerError),
+    PanicError(String, String),
+    SizeLimitError(String, u64, u64),
+    TimeoutError(String, Duration),
+    ManifestError(PathBuf, String),
+    ManifestNotFound(PathBuf),
+    ProjectNotFound(PathBuf),
+    EnvironmentError(String, String),
+    RemoteCacheError(String, BoxedError),
+    ChecksumError(PathBuf),
+    SourceNotFound(String),
+    InternalError(String, String),
+    SourceIoError(String, PathBuf, std::io::Error),
+    ConfigError(String),
 }
 
-impl From<walkdir::Error> for Error {
-    fn from(error: walkdir::Error) -> Self {
-        Error::IoError(error.into())
+impl Error {
+    /// Wrap an i/o error with the path of the file, or directory, it happened on.
+    pub fn io<P: AsRef<Path>>(path: P) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.as_ref().to_path_buf();
+
+        move |error| Error::IoError(path, error)
     }
-}
 
-impl From<std::io::Error> for Error {
-    fn from(error: std::io::Error) -> Self {
-        Error::IoError(error)
+    /// The issue code used when the error is reported, parse errors carry their own codes.
+    pub fn code(&self) -> Option<&'static str> {
+        let code = match self {
+            Error::CacheMiss(..) => "F0001",
+            Error::SerializeError(..) => "F0002",
+            Error::DeserializeError(..) => "F0003",
+            Error::InvalidPath(..) => "F0004",
+            Error::IoError(..) => "F0005",
+            Error::ParseError(..) => return None,
+            Error::LogError(..) => "F0006",
+            Error::PanicError(..) => "F0007",
+            Error::SizeLimitError(..) => "F0008",
+            Error::TimeoutError(..) => "F0009",
+            Error::ManifestError(..) => "F0010",
+            Error::ManifestNotFound(..) => "F0011",
+            Error::ProjectNotFound(..) => "F0012",
+            Error::EnvironmentError(..) => "F0013",
+            Error::RemoteCacheError(..) => "F0014",
+            Error::ChecksumError(..) => "F0015",
+            Error::SourceNotFound(..) => "F0016",
+            Error::InternalError(..) => "F0017",
+            Error::SourceIoError(..) => "F0018",
+            Error::ConfigError(..) => "F0019",
+        };
+
+        Some(code)
     }
-}
 
-impl From<Error> for Report {
-    fn from(error: Error) -> Self {
-        Report::new().with_issue(Issue::from_string(error.to_string()))
+    /// The origin of the source the error is about, if any.
+    pub fn origin(&self) -> Option<&str> {
+        match self {
+            Error::CacheMiss(origin)
+            | Error::PanicError(origin, _)
+            | Error::InternalError(origin, _)
+            | Error::SourceIoError(origin, ..)
+            | Error::SizeLimitError(origin, ..)
+            | Error::TimeoutError(origin, _) => Some(origin),
+            _ => None,
+        }
     }
-}
 
-impl From<bincode::error::EncodeError> for Error {
-    fn from(error: bincode::error::EncodeError) -> Self {
-        Error::SerializeError(error.to_string())
+    /// The path of the file, or directory, the error is about, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::SerializeError(path, _)
+            | Error::DeserializeError(path, _)
+            | Error::InvalidPath(path)
+            | Error::IoError(path, _)
+            | Error::SourceIoError(_, path, _)
+            | Error::ManifestError(path, _)
+            | Error::ManifestNotFound(path)
+            | Error::ProjectNotFound(path)
+            | Error::ChecksumError(path) => Some(path),
+            _ => None,
+        }
     }
 }
 
-impl From<bincode::error::DecodeError> for Error {
-    fn from(error: bincode::error::DecodeError) -> Self {
-        Error::DeserializeError(error.to_string())
+impl From<walkdir::Error> for Error {
+    fn from(error: walkdir::Error) -> Self {
+        let path = error.path().map(Path::to_path_buf).unwrap_or_default();
+
+        Error::IoError(path, error.into())
     }
 }
 
@@ -48,16 +112,116 @@ impl From<log::SetLoggerError> for Error {
     }
 }
 
+impl From<Error> for Box<Report> {
+    fn from(error: Error) -> Self {
+        if let Error::ParseError(report) = error {
+            return report;
+        }
+
+        let mut issue = match &error {
+            Error::PanicError(origin, _) => Issue::new(IssueSeverity::Bug, error.to_string()).with_note(format!(
+                "this is a bug, please report it to https://github.com/ara-lang/ara/issues along with the contents of `{origin}`."
+            )),
+            Error::InternalError(..) => Issue::new(IssueSeverity::Bug, error.to_string()).with_note(
+                "this is a bug, please report it to https://github.com/ara-lang/forest/issues.",
+            ),
+            _ => Issue::new(IssueSeverity::Error, error.to_string()),
+        };
+
+        if let Some(code) = error.code() {
+            issue = issue.with_code(code);
+        }
+
+        if let Some(origin) = error.origin() {
+            issue = issue.with_source(origin, 0, 0);
+        }
+
+        let mut cause = std::error::Error::source(&error);
+        while let Some(error) = cause {
+            issue = issue.with_note(format!("caused by: {error}"));
+            cause = error.source();
+        }
+
+        Box::new(Report::new().with_issue(issue))
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::SerializeError(_, error)
+            | Error::DeserializeError(_, error)
+            | Error::RemoteCacheError(_, error) => Some(&**error),
+            Error::IoError(_, error) | Error::SourceIoError(_, _, error) => Some(error),
+            Error::LogError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::IoError(error) => write!(f, "io error: {error}"),
-            Error::InvalidPath(message) => write!(f, "invalid source: {message}"),
-            Error::SerializeError(message) => write!(f, "serialize error: {message}"),
-            Error::DeserializeError(message) => write!(f, "deserialize error: {message}"),
+            Error::CacheMiss(origin) => write!(f, "cache miss for source {origin}"),
+            Error::SerializeError(path, _) => {
+                write!(f, "failed to serialize cache file {}", path.display())
+            }
+            Error::DeserializeError(path, _) => {
+                write!(f, "failed to deserialize cache file {}", path.display())
+            }
+            Error::InvalidPath(path) => write!(
+                f,
+                "{} must be a directory and be relative to the project root directory.",
+                path.display()
+            ),
+            Error::IoError(path, _) => write!(f, "i/o error on {}", path.display()),
             Error::ParseError(report) => write!(f, "parse error: {report}"),
-            Error::LogError(error) => write!(f, "log error: {error}"),
-            Error::CacheMiss => write!(f, "cache miss"),
+            Error::LogError(_) => write!(f, "failed to initialize the logger"),
+            Error::PanicError(origin, message) => {
+                write!(f, "the parser panicked while parsing {origin}: {message}")
+            }
+            Error::SizeLimitError(origin, size, max_file_size) => write!(
+                f,
+                "source {origin} is {size} bytes, which exceeds the maximum file size of {max_file_size} bytes"
+            ),
+            Error::TimeoutError(origin, timeout) => write!(
+                f,
+                "parsing source {origin} took longer than the timeout of {timeout:?}"
+            ),
+            Error::ManifestError(path, message) => {
+                write!(f, "invalid manifest {}: {message}", path.display())
+            }
+            Error::ManifestNotFound(path) => write!(
+                f,
+                "could not find an `ara.toml` or `ara.json` manifest in {}",
+                path.display()
+            ),
+            Error::ProjectNotFound(path) => write!(
+                f,
+                "{} is not inside a project, no `ara.toml` or `ara.json` manifest was found in it or any of its parent directories",
+                path.display()
+            ),
+            Error::EnvironmentError(name, message) => {
+                write!(f, "invalid environment variable `{name}`: {message}")
+            }
+            Error::RemoteCacheError(key, _) => {
+                write!(f, "remote cache request for entry {key} failed")
+            }
+            Error::ChecksumError(path) => write!(
+                f,
+                "cache file {} is corrupt, its checksum doesn't match its content",
+                path.display()
+            ),
+            Error::SourceNotFound(origin) => {
+                write!(f, "source {origin} is not part of the project")
+            }
+            Error::InternalError(origin, message) => {
+                write!(f, "panicked while building source {origin}: {message}")
+            }
+            Error::SourceIoError(origin, path, _) => {
+                write!(f, "i/o error on source {origin} ({})", path.display())
+            }
+            Error::ConfigError(message) => write!(f, "invalid configuration: {message}"),
         }
     }
 }
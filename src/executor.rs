@@ -0,0 +1,91 @@
+use std::thread;
+
+pub type Job<'scope> = Box<dyn FnOnce() + Send + 'scope>;
+
+/// Runs the parsing jobs of a [`crate::Parser`].
+///
+/// Jobs borrow from the parser, so they can't be handed to a pool as `'static` tasks,
+/// they must run within a scope that ends before `execute` returns, e.g. `std::thread::scope`,
+/// or `rayon::in_place_scope`.
+pub trait Executor: Send + Sync {
+    /// Start the given jobs concurrently, and call `wait` on the current thread while they
+    /// run, returning once `wait`, and all of the jobs, are done.
+    ///
+    /// `wait` consumes the results of the jobs, so it must run while they do, otherwise
+    /// the jobs block once their results fill up.
+    fn execute<'scope>(&self, jobs: Vec<Job<'scope>>, wait: &mut dyn FnMut());
+}
+
+/// Runs each job on its own scoped thread.
+pub struct ThreadExecutor;
+
+impl ThreadExecutor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ThreadExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Executor for ThreadExecutor {
+    fn execute<'scope>(&self, jobs: Vec<Job<'scope>>, wait: &mut dyn FnMut()) {
+        thread::scope(|scope| {
+            for job in jobs {
+                scope.spawn(job);
+            }
+
+            wait();
+        });
+    }
+}
+
+/// Runs the jobs on a rayon thread pool, or the global one if none is given.
+#[cfg(feature = "rayon")]
+pub struct RayonExecutor {
+    pool: Option<std::sync::Arc<rayon::ThreadPool>>,
+}
+
+#[cfg(feature = "rayon")]
+impl RayonExecutor {
+    pub fn new(pool: std::sync::Arc<rayon::ThreadPool>) -> Self {
+        Self { pool: Some(pool) }
+    }
+
+    pub fn global() -> Self {
+        Self { pool: None }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Executor for RayonExecutor {
+    fn execute<'scope>(&self, jobs: Vec<Job<'scope>>, wait: &mut dyn FnMut()) {
+        let inside_pool = match &self.pool {
+            Some(pool) => pool.current_thread_index().is_some(),
+            None => rayon::current_thread_index().is_some(),
+        };
+
+        // `wait` blocks the current thread, called from a worker of the pool, it could hold
+        // the very worker the jobs are queued for, so they run on their own threads instead.
+        if inside_pool {
+            return ThreadExecutor::new().execute(jobs, wait);
+        }
+
+        let run = |scope: &rayon::Scope<'scope>| {
+            for job in jobs {
+                scope.spawn(move |_| job());
+            }
+
+            wait();
+        };
+
+        // the scope runs in place, so `wait` is called on the current thread, not on a worker.
+        match &self.pool {
+            Some(pool) => pool.in_place_scope(run),
+            None => rayon::in_place_scope(run),
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use std::fs;
+use std::path::PathBuf;
+
+use ara_reporting::issue::Issue;
+use ara_reporting::Report;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::source::SourceFilesCollector;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    /// SARIF 2.1.0, as consumed by code scanning dashboards.
+    Sarif,
+    Checkstyle,
+    JUnit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub end_line: Option<usize>,
+    pub end_column: Option<usize>,
+    pub severity: String,
+    pub code: Option<String>,
+    pub message: String,
+}
+
+pub struct ReportExporter {
+    root: PathBuf,
+    origins: Vec<String>,
+}
+
+impl ReportExporter {
+    /// Create an exporter, reading the contents of the files referenced by reports from the
+    /// given root directory, and listing the given origins as files that have been checked.
+    pub fn new<R: Into<PathBuf>>(root: R, origins: Vec<String>) -> Self {
+        Self {
+            root: root.into(),
+            origins,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        let origins = SourceFilesCollector::new(config)
+            .collect()?
+            .into_iter()
+            .map(|file| {
+                file.strip_prefix(&config.root)
+                    .unwrap_or(&file)
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+
+        Ok(Self::new(&config.root, origins))
+    }
+
+    pub fn export(&self, report: &Report, format: ExportFormat) -> String {
+        let diagnostics = self.diagnostics(report);
+
+        match format {
+            ExportFormat::Json => json!(diagnostics).to_string(),
+            ExportFormat::Sarif => self.sarif(&diagnostics),
+            ExportFormat::Checkstyle => self.checkstyle(&diagnostics),
+            ExportFormat::JUnit => self.junit(&diagnostics),
+        }
+    }
+
+    pub fn diagnostics(&self, report: &Report) -> Vec<Diagnostic> {
+        let mut contents = FxHashMap::default();
+
+        report
+            .issues
+            .iter()
+            .map(|issue| {
+                let mut diagnostic = Diagnostic::from(issue);
+
+                if let Some((origin, from, to)) = &issue.source {
+                    let content = contents
+                        .entry(origin.clone())
+                        .or_insert_with(|| fs::read_to_string(self.root.join(origin)).ok());
+
+                    if let Some(content) = content {
+                        let (line, column) = position(content, *from);
+                        let (end_line, end_column) = position(content, *to);
+
+                        diagnostic.line = Some(line);
+                        diagnostic.column = Some(column);
+                        diagnostic.end_line = Some(end_line);
+                        diagnostic.end_column = Some(end_column);
+                    }
+                }
+
+                diagnostic
+            })
+            .collect()
+    }
+
+    fn sarif(&self, diagnostics: &[Diagnostic]) -> String {
+        let mut rules = diagnostics
+            .iter()
+            .filter_map(|diagnostic| diagnostic.code.clone())
+            .collect::<Vec<_>>();
+        rules.sort();
+        rules.dedup();
+
+        let results = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let mut result = json!({
+                    "level": match diagnostic.severity.as_str() {
+                        "error" | "bug" => "error",
+                        "warning" => "warning",
+                        _ => "note",
+                    },
+                    "message": { "text": diagnostic.message },
+                });
+
+                if let Some(code) = &diagnostic.code {
+                    result["ruleId"] = json!(code);
+                }
+
+                if let Some(file) = &diagnostic.file {
+                    let mut location = json!({
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": file },
+                        },
+                    });
+
+                    if let (Some(line), Some(column), Some(end_line), Some(end_column)) = (
+                        diagnostic.line,
+                        diagnostic.column,
+                        diagnostic.end_line,
+                        diagnostic.end_column,
+                    ) {
+                        location["physicalLocation"]["region"] = json!({
+                            "startLine": line,
+                            "startColumn": column,
+                            "endLine": end_line,
+                            "endColumn": end_column,
+                        });
+                    }
+
+                    result["locations"] = json!([location]);
+                }
+
+                result
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "ara-forest",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                        "rules": rules.iter().map(|rule| json!({ "id": rule })).collect::<Vec<_>>(),
+                    },
+                },
+                "results": results,
+            }],
+        })
+        .to_string()
+    }
+
+    fn checkstyle(&self, diagnostics: &[Diagnostic]) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<checkstyle version=\"4.3\">\n");
+
+        for (file, diagnostics) in self.group(diagnostics) {
+            xml.push_str(&format!("  <file name=\"{}\">\n", escape(&file)));
+            for diagnostic in diagnostics {
+                xml.push_str(&format!(
+                    "    <error line=\"{}\" column=\"{}\" severity=\"{}\" message=\"{}\" source=\"{}\"/>\n",
+                    diagnostic.line.unwrap_or(0),
+                    diagnostic.column.unwrap_or(0),
+                    match diagnostic.severity.as_str() {
+                        "error" | "bug" => "error",
+                        "warning" => "warning",
+                        _ => "info",
+                    },
+                    escape(&diagnostic.message),
+                    escape(diagnostic.code.as_deref().unwrap_or_default()),
+                ));
+            }
+            xml.push_str("  </file>\n");
+        }
+
+        xml.push_str("</checkstyle>\n");
+
+        xml
+    }
+
+    fn junit(&self, diagnostics: &[Diagnostic]) -> String {
+        let groups = self.group(diagnostics);
+        let failures = groups
+            .iter()
+            .filter(|(_, diagnostics)| diagnostics.iter().any(|diagnostic| is_failure(diagnostic)))
+            .count();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"ara-forest\" tests=\"{}\" failures=\"{}\">\n",
+            groups.len(),
+            failures
+        ));
+        xml.push_str(&format!(
+            "  <testsuite name=\"ara-forest\" tests=\"{}\" failures=\"{}\">\n",
+            groups.len(),
+            failures
+        ));
+
+        for (file, diagnostics) in groups {
+            if diagnostics.is_empty() {
+                xml.push_str(&format!(
+                    "    <testcase name=\"{}\" classname=\"ara-forest\"/>\n",
+                    escape(&file)
+                ));
+
+                continue;
+            }
+
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"ara-forest\">\n",
+                escape(&file)
+            ));
+
+            let mut output = Vec::new();
+            for diagnostic in diagnostics {
+                let location = match (diagnostic.line, diagnostic.column) {
+                    (Some(line), Some(column)) => format!(" at {file}:{line}:{column}"),
+                    _ => String::new(),
+                };
+                let text = match &diagnostic.code {
+                    Some(code) => format!(
+                        "{}[{}]: {}{}",
+                        diagnostic.severity, code, diagnostic.message, location
+                    ),
+                    None => format!(
+                        "{}: {}{}",
+                        diagnostic.severity, diagnostic.message, location
+                    ),
+                };
+
+                if is_failure(diagnostic) {
+                    xml.push_str(&format!(
+                        "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                        escape(diagnostic.code.as_deref().unwrap_or(&diagnostic.severity)),
+                        escape(&diagnostic.message),
+                        escape(&text),
+                    ));
+                } else {
+                    output.push(text);
+                }
+            }
+
+            if !output.is_empty() {
+                xml.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    escape(&output.join("\n"))
+                ));
+            }
+
+            xml.push_str("    </testcase>\n");
+        }
+
+        xml.push_str("  </testsuite>\n");
+        xml.push_str("</testsuites>\n");
+
+        xml
+    }
+
+    // every checked file, along with its diagnostics, followed by the diagnostics
+    // that don't belong to any file.
+    fn group<'d>(&self, diagnostics: &'d [Diagnostic]) -> Vec<(String, Vec<&'d Diagnostic>)> {
+        let mut groups: Vec<(String, Vec<&Diagnostic>)> = self
+            .origins
+            .iter()
+            .map(|origin| (origin.clone(), Vec::new()))
+            .collect();
+
+        for diagnostic in diagnostics {
+            let file = diagnostic.file.clone().unwrap_or_default();
+            match groups.iter_mut().find(|(origin, _)| *origin == file) {
+                Some((_, group)) => group.push(diagnostic),
+                None => groups.push((file, vec![diagnostic])),
+            }
+        }
+
+        groups
+    }
+}
+
+fn is_failure(diagnostic: &Diagnostic) -> bool {
+    matches!(diagnostic.severity.as_str(), "error" | "bug")
+}
+
+// 1-based line and column, in characters, of the given byte offset.
+fn position(content: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(content.len());
+    while !content.is_char_boundary(offset) {
+        offset -= 1;
+    }
+
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = match before.rfind('\n') {
+        Some(newline) => before[newline + 1..].chars().count() + 1,
+        None => before.chars().count() + 1,
+    };
+
+    (line, column)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl From<&Issue> for Diagnostic {
+    fn from(issue: &Issue) -> Self {
+        Self {
+            file: issue.source.as_ref().map(|(origin, _, _)| origin.clone()),
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+            severity: issue.severity.to_string(),
+            code: issue.code.clone(),
+            message: issue.message.clone(),
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use ara_parser::tree::Tree;
+use ara_reporting::Report;
+use ara_reporting::ReportFooter;
+use ara_source::source::Source;
+
+use crate::config::Config;
+use crate::config::LimitPolicy;
+use crate::error::Error;
+use crate::symbol;
+use crate::symbol::SymbolIndex;
+use crate::tree::TreeBuilder;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LazyEntry {
+    pub origin: String,
+    pub signature: u64,
+    path: PathBuf,
+}
+
+/// A forest that knows the origin, and signature, of every source up front, but only
+/// loads their trees, from the cache or by parsing them, on first access.
+///
+/// Loaded trees are kept in memory, unless a memory limit is set, in which case the
+/// least recently used trees are evicted to stay under it.
+pub struct LazyForest<'a> {
+    tree_builder: TreeBuilder<'a>,
+    entries: Vec<LazyEntry>,
+    positions: HashMap<String, usize>,
+    memory_limit: Option<u64>,
+    loaded: Mutex<LoadedTrees>,
+}
+
+#[derive(Default)]
+struct LoadedTrees {
+    trees: HashMap<usize, LoadedTree>,
+    size: u64,
+    clock: u64,
+}
+
+struct LoadedTree {
+    tree: Arc<Tree>,
+    size: u64,
+    used: u64,
+}
+
+impl<'a> LazyForest<'a> {
+    pub(crate) fn collect(config: &'a Config, files: Vec<PathBuf>) -> Result<Self, Box<Report>> {
+        let tree_builder = TreeBuilder::new(config);
+        let mut entries = Vec::with_capacity(files.len());
+        let mut issues = Vec::new();
+        for path in files {
+            match tree_builder.sign(&path) {
+                Ok((origin, signature)) => entries.push(LazyEntry {
+                    origin,
+                    signature,
+                    path,
+                }),
+                Err(error @ Error::SizeLimitError(..))
+                    if config.limit_policy == LimitPolicy::Skip =>
+                {
+                    log::warn!("skipping source: {}", error);
+                }
+                Err(error) => issues.append(&mut Box::<Report>::from(error).issues),
+            }
+        }
+
+        if !issues.is_empty() {
+            return Err(Box::new(Report {
+                issues,
+                footer: Some(ReportFooter::new(
+                    "failed to read the project due to the above issue(s)",
+                )),
+            }));
+        }
+
+        let positions = entries
+            .iter()
+            .enumerate()
+            .map(|(position, entry)| (entry.origin.clone(), position))
+            .collect();
+
+        Ok(Self {
+            tree_builder,
+            entries,
+            positions,
+            memory_limit: None,
+            loaded: Mutex::default(),
+        })
+    }
+
+    /// Evict the least recently used trees once the combined size of their sources
+    /// exceeds the given number of bytes.
+    ///
+    /// The most recently loaded tree is always kept, even if it exceeds the limit on its own.
+    #[must_use]
+    pub fn with_memory_limit(mut self, bytes: u64) -> Self {
+        self.memory_limit = Some(bytes);
+
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The sources of the forest, in the same order as [`crate::Parser::parse`].
+    pub fn entries(&self) -> &[LazyEntry] {
+        &self.entries
+    }
+
+    pub fn contains(&self, origin: &str) -> bool {
+        self.positions.contains_key(origin)
+    }
+
+    /// The signature of the given source, as it was when the forest was created.
+    pub fn signature(&self, origin: &str) -> Option<u64> {
+        self.positions
+            .get(origin)
+            .map(|position| self.entries[*position].signature)
+    }
+
+    /// Whether the tree of the given source is currently held in memory.
+    pub fn is_loaded(&self, origin: &str) -> bool {
+        self.positions
+            .get(origin)
+            .is_some_and(|position| self.loaded.lock().unwrap().trees.contains_key(position))
+    }
+
+    /// Read the given source from disk.
+    pub fn source(&self, origin: &str) -> Result<Source, Error> {
+        let entry = self.entry(origin)?;
+
+        self.tree_builder.build_source(&entry.path)
+    }
+
+    /// The tree of the given source, loading it from the cache, or parsing it, if it isn't
+    /// already held in memory.
+    pub fn get(&self, origin: &str) -> Result<Arc<Tree>, Error> {
+        let position = *self
+            .positions
+            .get(origin)
+            .ok_or_else(|| Error::SourceNotFound(origin.to_string()))?;
+
+        if let Some(tree) = self.loaded.lock().unwrap().touch(position) {
+            return Ok(tree);
+        }
+
+        // the lock isn't held while building, so other trees can be loaded in parallel.
+        let (source, tree) = self.tree_builder.build(&self.entries[position].path)?;
+        let tree = Arc::new(tree);
+
+        let mut loaded = self.loaded.lock().unwrap();
+        if let Some(tree) = loaded.touch(position) {
+            return Ok(tree);
+        }
+
+        loaded.insert(position, tree.clone(), source.content.len() as u64);
+        if let Some(memory_limit) = self.memory_limit {
+            loaded.evict(memory_limit);
+        }
+
+        Ok(tree)
+    }
+
+    /// Index the symbols declared by every source, reading them from the cache where
+    /// possible, and only loading the trees of the sources whose symbols aren't cached.
+    pub fn symbols(&self) -> Result<SymbolIndex, Error> {
+        let mut symbols = Vec::new();
+        for entry in &self.entries {
+            let declarations = match self
+                .tree_builder
+                .get_declarations_from_cache(&entry.origin, entry.signature)
+            {
+                Some(declarations) => declarations,
+                None => {
+                    // the source could have changed since the forest was created, so its
+                    // symbols aren't cached under the signature known up front.
+                    symbol::declarations(&*self.get(&entry.origin)?)
+                }
+            };
+
+            symbols.extend(
+                declarations
+                    .into_iter()
+                    .map(|declaration| declaration.into_symbol(&entry.origin)),
+            );
+        }
+
+        Ok(SymbolIndex::new(symbols))
+    }
+
+    /// Drop every tree held in memory.
+    pub fn unload(&self) {
+        *self.loaded.lock().unwrap() = LoadedTrees::default();
+    }
+
+    fn entry(&self, origin: &str) -> Result<&LazyEntry, Error> {
+        self.positions
+            .get(origin)
+            .map(|position| &self.entries[*position])
+            .ok_or_else(|| Error::SourceNotFound(origin.to_string()))
+    }
+}
+
+impl LoadedTrees {
+    fn touch(&mut self, position: usize) -> Option<Arc<Tree>> {
+        self.clock += 1;
+
+        let loaded = self.trees.get_mut(&position)?;
+        loaded.used = self.clock;
+
+        Some(loaded.tree.clone())
+    }
+
+    fn insert(&mut self, position: usize, tree: Arc<Tree>, size: u64) {
+        self.clock += 1;
+        self.size += size;
+        self.trees.insert(
+            position,
+            LoadedTree {
+                tree,
+                size,
+                used: self.clock,
+            },
+        );
+    }
+
+    fn evict(&mut self, memory_limit: u64) {
+        while self.size > memory_limit && self.trees.len() > 1 {
+            let position = self
+                .trees
+                .iter()
+                .min_by_key(|(_, loaded)| loaded.used)
+                .map(|(position, _)| *position)
+                .unwrap();
+
+            let evicted = self.trees.remove(&position).unwrap();
+            self.size -= evicted.size;
+        }
+    }
+}
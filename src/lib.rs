@@ -1,39 +1,181 @@
+use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 use std::path::PathBuf;
-use std::thread;
+use std::sync::atomic::AtomicUsize;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
 
 use ara_parser::tree::Tree;
 use ara_parser::tree::TreeMap;
 use ara_reporting::Report;
+use ara_reporting::ReportFooter;
 use ara_source::source::Source;
+use ara_source::source::SourceKind;
 use ara_source::SourceMap;
 
+use crate::cache::WarmStats;
 use crate::config::Config;
 use crate::error::Error;
+use crate::executor::Job;
+use crate::lazy::LazyForest;
 use crate::source::SourceFilesCollector;
+use crate::stream::IndexedResult;
+use crate::stream::TreeIter;
+use crate::symbol::SymbolIndex;
 use crate::tree::TreeBuilder;
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod cache;
+pub(crate) mod checksum;
 pub mod config;
 pub mod error;
+pub mod executor;
+pub mod export;
 pub(crate) mod hash;
+pub mod lazy;
 pub mod logger;
-pub(crate) mod serializer;
+pub mod manifest;
+pub mod remote;
+pub mod serializer;
 pub mod source;
-pub(crate) mod tree;
+pub mod stream;
+pub mod symbol;
+pub mod tree;
+pub mod vendor;
 
 pub(crate) const ARA_SOURCE_EXTENSION: &str = "ara";
 pub(crate) const ARA_DEFINITION_EXTENSION: &str = "d.ara";
 pub(crate) const ARA_CACHED_SOURCE_EXTENSION: &str = "ara.cache";
+pub(crate) const ARA_CACHED_SYMBOLS_EXTENSION: &str = "ara.symbols";
+pub(crate) const ARA_CACHE_INDEX_DIRECTORY: &str = "index";
+// must be kept in sync with the `ara_parser` dependency, which is pinned to an exact
+// version, as trees produced by a different version of the parser must not be shared
+// through content addressed keys.
+pub(crate) const ARA_PARSER_VERSION: &str = "0.6.6";
 
 #[derive(Debug)]
 pub struct Forest {
     pub source: SourceMap,
     pub tree: TreeMap,
+    positions: HashMap<String, usize>,
+    symbols: Option<SymbolIndex>,
 }
 
 impl Forest {
     pub fn new(source: SourceMap, tree: TreeMap) -> Self {
-        Self { source, tree }
+        let positions = source
+            .sources
+            .iter()
+            .enumerate()
+            .filter_map(|(position, source)| Some((source.origin.clone()?, position)))
+            .collect();
+
+        Self {
+            source,
+            tree,
+            positions,
+            symbols: None,
+        }
+    }
+
+    /// Index the symbols declared by the trees of the forest.
+    #[cfg(feature = "async")]
+    pub(crate) fn index_symbols(&mut self) {
+        let symbols = self
+            .iter()
+            .flat_map(|(source, tree)| {
+                let origin = source.origin.as_ref().unwrap();
+
+                symbol::declarations(tree)
+                    .into_iter()
+                    .map(|declaration| declaration.into_symbol(origin))
+            })
+            .collect();
+
+        self.symbols = Some(SymbolIndex::new(symbols));
+    }
+
+    /// The symbols declared by the sources of the forest, if the parser was configured
+    /// to index them, see [`Config::with_symbol_index`].
+    pub fn symbols(&self) -> Option<&SymbolIndex> {
+        self.symbols.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.source.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.source.sources.is_empty()
+    }
+
+    pub fn contains(&self, origin: &str) -> bool {
+        self.get(origin).is_some()
+    }
+
+    /// The source, and tree, with the given origin.
+    pub fn get(&self, origin: &str) -> Option<(&Source, &Tree)> {
+        let is = |source: &Source| source.origin.as_deref() == Some(origin);
+
+        // the sources, and trees, are public, so they could have been reordered, or removed,
+        // since the positions were recorded, in which case they are searched instead.
+        let position = self
+            .positions
+            .get(origin)
+            .copied()
+            .filter(|position| self.source.sources.get(*position).is_some_and(is))
+            .or_else(|| self.source.sources.iter().position(is))?;
+
+        Some((
+            &self.source.sources[position],
+            self.tree.trees.get(position)?,
+        ))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Source, &Tree)> {
+        self.source.sources.iter().zip(&self.tree.trees)
+    }
+
+    /// The definition sources, and their trees.
+    pub fn definitions(&self) -> impl Iterator<Item = (&Source, &Tree)> {
+        self.iter()
+            .filter(|(source, _)| source.kind == SourceKind::Definition)
+    }
+
+    /// The script sources, and their trees.
+    pub fn scripts(&self) -> impl Iterator<Item = (&Source, &Tree)> {
+        self.iter()
+            .filter(|(source, _)| source.kind == SourceKind::Script)
+    }
+
+    pub(crate) fn collect(mut results: Vec<IndexedResult>) -> Result<Self, Box<Report>> {
+        results.sort_by_key(|(index, _)| *index);
+
+        let mut sources = Vec::with_capacity(results.len());
+        let mut trees = Vec::with_capacity(results.len());
+        let mut issues = Vec::new();
+        for (_, result) in results {
+            match result {
+                Ok((source, tree)) => {
+                    sources.push(source);
+                    trees.push(tree);
+                }
+                Err(error) => issues.append(&mut Box::<Report>::from(error).issues),
+            }
+        }
+
+        if !issues.is_empty() {
+            return Err(Box::new(Report {
+                issues,
+                footer: Some(ReportFooter::new(
+                    "failed to parse the project due to the above issue(s)",
+                )),
+            }));
+        }
+
+        Ok(Self::new(SourceMap::new(sources), TreeMap::new(trees)))
     }
 }
 
@@ -43,69 +185,185 @@ pub struct Parser<'a> {
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(config: &'a Config) -> Self {
+    pub fn new(config: &'a Config) -> Result<Self, Box<Report>> {
+        config.validate()?;
+
+        Ok(Self::unchecked(config))
+    }
+
+    pub(crate) fn unchecked(config: &'a Config) -> Self {
         Parser {
             config,
             tree_builder: TreeBuilder::new(config),
         }
     }
 
+    fn indexing(config: &'a Config) -> Self {
+        Parser {
+            config,
+            tree_builder: TreeBuilder::new(config).with_symbol_index(),
+        }
+    }
+
+    fn caching_symbols(config: &'a Config) -> Self {
+        let mut tree_builder = TreeBuilder::new(config);
+        if config.symbol_index {
+            tree_builder = tree_builder.with_symbol_cache();
+        }
+
+        Parser {
+            config,
+            tree_builder,
+        }
+    }
+
     pub fn parse(&self) -> Result<Forest, Box<Report>> {
-        self.init_logger().map_err(|error| Box::new(error.into()))?;
+        if !self.config.symbol_index {
+            return Forest::collect(self.stream(TreeBuilder::build_indexed, |receiver| {
+                receiver.into_iter().collect()
+            })?);
+        }
 
-        let (sources, trees) =
-            thread::scope(|scope| -> Result<(Vec<Source>, Vec<Tree>), Box<Report>> {
-                self.create_cache_dir()
-                    .map_err(|error| Box::new(error.into()))?;
+        // a dedicated parser, so the index only covers the symbols of this run.
+        let parser = Parser::indexing(self.config);
+        let mut forest =
+            Forest::collect(parser.stream(TreeBuilder::build_indexed, |receiver| {
+                receiver.into_iter().collect()
+            })?)?;
+        forest.symbols = Some(SymbolIndex::new(parser.tree_builder.take_symbols()));
 
-                let files = SourceFilesCollector::new(self.config)
-                    .collect()
-                    .map_err(|error| Box::new(error.into()))?;
+        Ok(forest)
+    }
 
-                if files.is_empty() {
-                    return Ok((Vec::new(), Vec::new()));
-                }
+    /// Parse the project, handing each tree to the consumer as soon as it is ready.
+    ///
+    /// The trees are yielded in the order they are parsed, and only a few of them
+    /// are buffered at a time. If the consumer stops early, the remaining files are
+    /// not parsed.
+    ///
+    /// If symbols are indexed, they are cached alongside the trees, but not collected,
+    /// see [`LazyForest::symbols`] to index them.
+    pub fn parse_iter<F, R>(&self, consumer: F) -> Result<R, Box<Report>>
+    where
+        F: FnOnce(TreeIter) -> R,
+    {
+        if !self.config.symbol_index {
+            return self.stream(TreeBuilder::build_indexed, |receiver| {
+                consumer(TreeIter::new(receiver))
+            });
+        }
 
-                let threads_count = self.threads_count(files.len());
-                let chunks = files
-                    .chunks(files.len() / threads_count)
-                    .map(Vec::from)
-                    .collect::<Vec<Vec<PathBuf>>>();
-
-                let mut threads = Vec::with_capacity(threads_count);
-                for chunk in chunks.into_iter() {
-                    threads.push(scope.spawn(
-                        move || -> Result<Vec<(Source, Tree)>, Box<Report>> {
-                            let mut source_tree = Vec::with_capacity(chunk.len());
-                            for source_path in chunk {
-                                let (source, tree) = self
-                                    .tree_builder
-                                    .build(&source_path)
-                                    .map_err(|error| match error {
-                                        Error::ParseError(report) => report,
-                                        _ => Box::new(error.into()),
-                                    })?;
-                                source_tree.push((source, tree));
-                            }
-
-                            Ok(source_tree)
-                        },
-                    ));
-                }
+        Parser::caching_symbols(self.config).stream(TreeBuilder::build_indexed, |receiver| {
+            consumer(TreeIter::new(receiver))
+        })
+    }
+
+    /// Collect the origin, and signature, of every source, leaving their trees to be
+    /// loaded on first access, see [`LazyForest`].
+    pub fn parse_lazy(&self) -> Result<LazyForest<'a>, Box<Report>> {
+        LazyForest::collect(self.config, self.prepare()?)
+    }
 
-                let mut result = Vec::new();
-                for handle in threads {
-                    result.extend(handle.join().unwrap()?);
+    /// Parse a single file of the project, going through the cache like `parse` does.
+    pub fn parse_file(&self, source_path: &Path) -> Result<(Source, Tree), Box<Report>> {
+        let source_path = if source_path.is_relative() {
+            self.config.root.join(source_path)
+        } else {
+            source_path.to_path_buf()
+        };
+
+        self.init_logger()
+            .and_then(|_| self.create_cache_dir())
+            .and_then(|_| self.tree_builder.build(&source_path))
+            .map_err(Box::<Report>::from)
+    }
+
+    /// Refresh the cache, without building a forest.
+    ///
+    /// Each tree is dropped as soon as it is cached, so memory stays flat regardless of
+    /// the size of the project, the returned report holds the issues of the files that
+    /// failed to parse.
+    pub fn warm_cache(&self) -> Result<(WarmStats, Report), Box<Report>> {
+        if self.config.cache.is_none() || self.config.cache_read_only {
+            return Err(Box::<Report>::from(Error::ConfigError(
+                "warming the cache requires a writable cache directory".to_string(),
+            )));
+        }
+
+        // a dedicated parser, so the cache counters only cover this run, symbols are
+        // cached, but not collected, so memory stays flat.
+        let parser = Parser::caching_symbols(self.config);
+        let mut stats = WarmStats::default();
+        let mut report = Report::new();
+
+        parser.stream(TreeBuilder::warm, |receiver| {
+            for (_, result) in receiver {
+                stats.files += 1;
+                if let Err(error) = result {
+                    stats.failures += 1;
+                    report.issues.append(&mut Box::<Report>::from(error).issues);
                 }
-                let (sources, trees) = result.into_iter().unzip();
+            }
+        })?;
+
+        (stats.hits, stats.misses) = parser.tree_builder.cache_counters();
+        if !report.issues.is_empty() {
+            report.footer = Some(ReportFooter::new(
+                "failed to warm the cache due to the above issue(s)",
+            ));
+        }
 
-                Ok((sources, trees))
-            })?;
+        Ok((stats, report))
+    }
+
+    fn stream<T, F, R>(
+        &self,
+        build: fn(&TreeBuilder<'a>, &Path) -> Result<T, Error>,
+        consumer: F,
+    ) -> Result<R, Box<Report>>
+    where
+        T: Send,
+        F: FnOnce(Receiver<(usize, Result<T, Error>)>) -> R,
+    {
+        let files = self.prepare()?;
+        let threads_count = self.threads_count(files.len());
+        let (sender, receiver) = mpsc::sync_channel(threads_count * 2);
+        let cursor = AtomicUsize::new(0);
+        let jobs = (0..threads_count)
+            .map(|_| {
+                let sender = sender.clone();
+                let (files, cursor) = (&files, &cursor);
+
+                Box::new(move || {
+                    self.tree_builder
+                        .build_all(files, cursor, build, |index, result| {
+                            sender.send((index, result)).is_ok()
+                        });
+                }) as Job
+            })
+            .collect::<Vec<Job>>();
+
+        drop(sender);
+
+        let mut consumer = Some((consumer, receiver));
+        let mut result = None;
+        self.config.executor.execute(jobs, &mut || {
+            if let Some((consumer, receiver)) = consumer.take() {
+                result = Some(consumer(receiver));
+            }
+        });
 
-        Ok(Forest::new(SourceMap::new(sources), TreeMap::new(trees)))
+        Ok(result.expect("the executor did not wait for the jobs"))
     }
 
-    fn threads_count(&self, files_len: usize) -> usize {
+    pub(crate) fn prepare(&self) -> Result<Vec<PathBuf>, Error> {
+        self.init_logger()?;
+        self.create_cache_dir()?;
+
+        SourceFilesCollector::new(self.config).collect()
+    }
+
+    pub(crate) fn threads_count(&self, files_len: usize) -> usize {
         if self.config.threads > files_len {
             files_len
         } else {
@@ -114,16 +372,21 @@ impl<'a> Parser<'a> {
     }
 
     fn create_cache_dir(&self) -> Result<(), Error> {
-        if self.config.cache.is_some() {
-            fs::create_dir_all(self.config.cache.as_ref().unwrap())?;
+        if let Some(cache) = &self.config.cache {
+            if !self.config.cache_read_only {
+                if let Err(error) = fs::create_dir_all(cache) {
+                    self.tree_builder
+                        .handle_cache_failure(Error::IoError(cache.clone(), error))?;
+                }
+            }
         }
 
         Ok(())
     }
 
     fn init_logger(&self) -> Result<(), Error> {
-        if self.config.logger.is_some() {
-            self.config.logger.as_ref().unwrap().init()?
+        if let Some(logger) = &self.config.logger {
+            logger.init()?
         }
 
         Ok(())
@@ -1,10 +1,17 @@
+use serde::Deserialize;
+use serde::Deserializer;
 use simplelog::*;
 use std::fmt::Debug;
 use std::fs::File;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
 
 use crate::error::Error;
 
+// a process can only install a single logger, so it is only installed by the first parse.
+static INSTALLED: Mutex<bool> = Mutex::new(false);
+
 #[repr(usize)]
 #[derive(Debug, Copy, Clone)]
 pub enum LogLevel {
@@ -44,25 +51,33 @@ impl Logger {
         self
     }
 
+    /// Install the logger, unless a logger was already installed by a previous call, in
+    /// which case that logger is kept.
     pub fn init(&self) -> Result<(), Error> {
+        let mut installed = INSTALLED.lock().unwrap();
+        if *installed {
+            return Ok(());
+        }
+
         let level = self.level.unwrap_or(LogLevel::Off);
 
         let mut loggers: Vec<Box<dyn SharedLogger>> = vec![TermLogger::new(
             level.into(),
             Config::default(),
-            TerminalMode::Mixed,
+            TerminalMode::Stderr,
             ColorChoice::Auto,
         )];
 
-        if self.file.is_some() {
+        if let Some(file) = &self.file {
             loggers.push(WriteLogger::new(
                 level.into(),
                 Config::default(),
-                File::create(self.file.as_ref().unwrap())?,
+                File::create(file).map_err(Error::io(file))?,
             ));
         }
 
         CombinedLogger::init(loggers)?;
+        *installed = true;
 
         Ok(())
     }
@@ -86,3 +101,29 @@ impl From<LogLevel> for LevelFilter {
         }
     }
 }
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level.to_lowercase().as_str() {
+            "off" => Ok(LogLevel::Off),
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(format!(
+                "unknown log level `{level}`, expected one of `off`, `error`, `warn`, `info`, `debug`, `trace`"
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LogLevel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::config::CacheKey;
+use crate::config::CacheValidation;
+use crate::config::Config;
+use crate::error::Error;
+use crate::logger::LogLevel;
+use crate::logger::Logger;
+
+pub const ARA_MANIFEST_FILES: [&str; 2] = ["ara.toml", "ara.json"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub source: Option<String>,
+    #[serde(default)]
+    pub definitions: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    pub vendor: Option<String>,
+    pub cache: Option<String>,
+    #[serde(default)]
+    pub cache_layers: Vec<String>,
+    pub cache_key: Option<CacheKey>,
+    pub cache_validation: Option<CacheValidation>,
+    pub threads: Option<usize>,
+    pub logger: Option<LoggerManifest>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoggerManifest {
+    pub level: Option<LogLevel>,
+    pub file: Option<String>,
+}
+
+impl Manifest {
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path).map_err(Error::io(path))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&content),
+            Some("json") => Self::from_json(&content),
+            _ => Err("unsupported manifest format, expected a `.toml` or `.json` file".to_string()),
+        }
+        .map_err(|message| Error::ManifestError(path.to_path_buf(), message))
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        serde_path_to_error::deserialize(toml::Deserializer::new(content)).map_err(describe)
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(content))
+            .map_err(describe)
+    }
+
+    pub fn configure(&self, mut config: Config) -> Config {
+        if let Some(source) = &self.source {
+            config = config.with_source(source);
+        }
+
+        if !self.definitions.is_empty() {
+            config = config.with_definitions(self.definitions.clone());
+        }
+
+        if !self.exclude.is_empty() {
+            config = config.with_excludes(self.exclude.clone());
+        }
+
+        if let Some(vendor) = &self.vendor {
+            config = config.with_vendor_directory(vendor);
+        }
+
+        if let Some(cache) = &self.cache {
+            config = config.with_cache_directory(cache);
+        }
+
+        if !self.cache_layers.is_empty() {
+            config = config.with_cache_layers(self.cache_layers.clone());
+        }
+
+        if let Some(key) = self.cache_key {
+            config = config.with_cache_key(key);
+        }
+
+        if let Some(validation) = self.cache_validation {
+            config = config.with_cache_validation(validation);
+        }
+
+        if let Some(threads) = self.threads {
+            config = config.with_threads(threads);
+        }
+
+        if let Some(logger) = &self.logger {
+            let logger = logger.configure(&config.root);
+            config = config.with_logger(logger);
+        }
+
+        config
+    }
+}
+
+impl LoggerManifest {
+    fn configure(&self, root: &Path) -> Logger {
+        let mut logger = Logger::new();
+
+        if let Some(level) = self.level {
+            logger = logger.with_level(level);
+        }
+
+        if let Some(file) = &self.file {
+            logger = logger.with_file(root.join(file));
+        }
+
+        logger
+    }
+}
+
+fn describe<E: Display>(error: serde_path_to_error::Error<E>) -> String {
+    match error.path().to_string().as_str() {
+        "." => error.inner().to_string(),
+        key => format!("invalid value for `{}`: {}", key, error.inner()),
+    }
+}
@@ -0,0 +1,75 @@
+#[cfg(feature = "remote")]
+use std::io::Read;
+#[cfg(feature = "remote")]
+use std::time::Duration;
+
+use crate::error::BoxedError;
+
+/// A cache shared between machines, addressed by the content key of its entries.
+///
+/// Local cache directories are looked up first, then the remote cache, and trees
+/// that had to be parsed are written back to both.
+pub trait RemoteCache: Send + Sync {
+    /// Fetch the entry with the given key, returning `None` if it doesn't exist.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, BoxedError>;
+
+    /// Store the entry with the given key, replacing any existing one.
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), BoxedError>;
+}
+
+/// A remote cache served over HTTP.
+///
+/// The protocol consists of two requests:
+///
+/// - `GET {url}/{key}`: responds with `200` and the entry as the body, or `404` if it doesn't exist.
+/// - `PUT {url}/{key}`: stores the body as the entry, responding with any `2xx` status.
+#[cfg(feature = "remote")]
+pub struct HttpRemoteCache {
+    url: String,
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "remote")]
+impl HttpRemoteCache {
+    pub fn new<U: Into<String>>(url: U) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(10))
+                .build(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.agent = ureq::AgentBuilder::new().timeout(timeout).build();
+
+        self
+    }
+
+    fn entry_url(&self, key: &str) -> String {
+        format!("{}/{}", self.url, key)
+    }
+}
+
+#[cfg(feature = "remote")]
+impl RemoteCache for HttpRemoteCache {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, BoxedError> {
+        match self.agent.get(&self.entry_url(key)).call() {
+            Ok(response) => {
+                let mut data = Vec::new();
+                response.into_reader().read_to_end(&mut data)?;
+
+                Ok(Some(data))
+            }
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(error) => Err(Box::new(error)),
+        }
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), BoxedError> {
+        self.agent.put(&self.entry_url(key)).send_bytes(data)?;
+
+        Ok(())
+    }
+}
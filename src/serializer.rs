@@ -1,12 +1,15 @@
 use bincode::config;
 use bincode::config::Configuration;
 
-use crate::error::Error;
+#[cfg(feature = "serde")]
+use ara_parser::tree::Tree;
+
+use crate::error::BoxedError;
 use crate::tree::SignedTree;
 
 pub trait Serializer: Send + Sync {
-    fn serialize(&self, signed_tree: &SignedTree) -> Result<Vec<u8>, Error>;
-    fn deserialize(&self, data: &[u8]) -> Result<SignedTree, Error>;
+    fn serialize(&self, signed_tree: &SignedTree) -> Result<Vec<u8>, BoxedError>;
+    fn deserialize(&self, data: &[u8]) -> Result<SignedTree, BoxedError>;
 }
 
 pub struct BincodeSerializer {
@@ -28,13 +31,113 @@ impl Default for BincodeSerializer {
 }
 
 impl Serializer for BincodeSerializer {
-    fn serialize(&self, tree: &SignedTree) -> Result<Vec<u8>, Error> {
+    fn serialize(&self, tree: &SignedTree) -> Result<Vec<u8>, BoxedError> {
         Ok(bincode::encode_to_vec(tree, self.config)?)
     }
 
-    fn deserialize(&self, data: &[u8]) -> Result<SignedTree, Error> {
+    fn deserialize(&self, data: &[u8]) -> Result<SignedTree, BoxedError> {
         let (signed_tree, _): (SignedTree, _) = bincode::decode_from_slice(data, self.config)?;
 
         Ok(signed_tree)
     }
 }
+
+#[cfg(feature = "serde")]
+pub struct JsonSerializer {
+    pretty: bool,
+}
+
+#[cfg(feature = "serde")]
+impl JsonSerializer {
+    pub fn new() -> Self {
+        Self { pretty: false }
+    }
+
+    #[must_use]
+    pub fn with_pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+
+        self
+    }
+
+    pub fn tree_to_string(&self, tree: &Tree) -> Result<String, serde_json::Error> {
+        let tree = json::Tree {
+            source: &tree.source,
+            definitions: &tree.definitions,
+        };
+
+        self.to_string(&tree)
+    }
+
+    fn to_string<T: serde::Serialize>(&self, value: &T) -> Result<String, serde_json::Error> {
+        if self.pretty {
+            serde_json::to_string_pretty(value)
+        } else {
+            serde_json::to_string(value)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Default for JsonSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serializer for JsonSerializer {
+    fn serialize(&self, signed_tree: &SignedTree) -> Result<Vec<u8>, BoxedError> {
+        let signed_tree = json::SignedTree {
+            signature: signed_tree.signature,
+            tree: json::Tree {
+                source: &signed_tree.tree.source,
+                definitions: &signed_tree.tree.definitions,
+            },
+        };
+
+        Ok(self.to_string(&signed_tree)?.into_bytes())
+    }
+
+    fn deserialize(&self, data: &[u8]) -> Result<SignedTree, BoxedError> {
+        let signed_tree: json::OwnedSignedTree = serde_json::from_slice(data)?;
+
+        Ok(SignedTree::new(
+            signed_tree.signature,
+            Tree::new(signed_tree.tree.source, signed_tree.tree.definitions),
+        ))
+    }
+}
+
+// `Tree` doesn't implement serde's traits, unlike the definitions it holds,
+// so it is mirrored here.
+#[cfg(feature = "serde")]
+mod json {
+    use ara_parser::tree::definition::DefinitionTree;
+    use serde::Deserialize;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    pub struct SignedTree<'a> {
+        pub signature: u64,
+        pub tree: Tree<'a>,
+    }
+
+    #[derive(Serialize)]
+    pub struct Tree<'a> {
+        pub source: &'a str,
+        pub definitions: &'a DefinitionTree,
+    }
+
+    #[derive(Deserialize)]
+    pub struct OwnedSignedTree {
+        pub signature: u64,
+        pub tree: OwnedTree,
+    }
+
+    #[derive(Deserialize)]
+    pub struct OwnedTree {
+        pub source: String,
+        pub definitions: DefinitionTree,
+    }
+}
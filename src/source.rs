@@ -3,6 +3,7 @@ use walkdir::WalkDir;
 
 use crate::config::Config;
 use crate::error::Error;
+use crate::vendor::VendorDefinitionsCollector;
 use crate::ARA_SOURCE_EXTENSION;
 
 pub struct SourceFilesCollector<'a> {
@@ -15,19 +16,35 @@ impl<'a> SourceFilesCollector<'a> {
     }
 
     pub fn collect(&self) -> Result<Vec<PathBuf>, Error> {
+        let vendor_definitions = VendorDefinitionsCollector::new(self.config).collect()?;
+
         let mut paths = vec![&self.config.source];
         paths.extend(&self.config.definitions);
+        for definition in &vendor_definitions {
+            if !paths.contains(&definition) {
+                paths.push(definition);
+            }
+        }
 
         let mut files = Vec::new();
         for path in paths {
             let path = &self.config.root.join(path);
             if !path.is_dir() {
-                return Err(Error::InvalidPath(format!(
-                    "{} must be a directory and be relative to the project root directory.",
-                    path.display(),
-                )));
+                return Err(Error::InvalidPath(path.to_path_buf()));
             }
-            for entry in WalkDir::new(path) {
+            let excludes = self
+                .config
+                .excludes
+                .iter()
+                .map(|exclude| self.config.root.join(exclude))
+                .collect::<Vec<_>>();
+            let entries = WalkDir::new(path).into_iter().filter_entry(|entry| {
+                !excludes
+                    .iter()
+                    .any(|exclude| entry.path().starts_with(exclude))
+            });
+
+            for entry in entries {
                 let entry = entry?;
                 if entry.file_type().is_file()
                     && entry.path().extension() == Some(ARA_SOURCE_EXTENSION.as_ref())
@@ -0,0 +1,26 @@
+use std::sync::mpsc::Receiver;
+
+use ara_parser::tree::Tree;
+use ara_source::source::Source;
+
+use crate::error::Error;
+
+pub(crate) type IndexedResult = (usize, Result<(Source, Tree), Error>);
+
+pub struct TreeIter {
+    receiver: Receiver<IndexedResult>,
+}
+
+impl TreeIter {
+    pub(crate) fn new(receiver: Receiver<IndexedResult>) -> Self {
+        Self { receiver }
+    }
+}
+
+impl Iterator for TreeIter {
+    type Item = Result<(Source, Tree), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok().map(|(_, result)| result)
+    }
+}
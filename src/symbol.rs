@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use ara_parser::tree::definition::r#enum::EnumDefinition;
+use ara_parser::tree::definition::Definition;
+use ara_parser::tree::Node;
+use ara_parser::tree::Tree;
+use bincode::Decode;
+use bincode::Encode;
+use serde::Serialize;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Encode, Decode)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Class,
+    Interface,
+    Enum,
+    Function,
+    Constant,
+    TypeAlias,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Symbol {
+    /// The fully qualified name of the symbol, without a leading backslash.
+    pub name: String,
+    pub kind: SymbolKind,
+    pub origin: String,
+    /// The start, and end, offsets of the symbol definition in its source.
+    pub span: (usize, usize),
+}
+
+/// The classes, interfaces, enums, functions, constants, and type aliases declared by
+/// the sources of a forest, indexed by their fully qualified names.
+#[derive(Debug, Default, Clone)]
+pub struct SymbolIndex {
+    symbols: HashMap<String, Vec<Symbol>>,
+}
+
+/// A symbol declared by a source, as it is cached alongside its tree.
+///
+/// The origin isn't part of the declaration, as sources with the same content can share
+/// a cache entry.
+#[derive(Debug, Clone, Encode, Decode)]
+pub(crate) struct Declaration {
+    name: String,
+    kind: SymbolKind,
+    span: (usize, usize),
+}
+
+#[derive(Encode, Decode)]
+pub(crate) struct SignedDeclarations {
+    pub signature: u64,
+    pub declarations: Vec<Declaration>,
+}
+
+impl SymbolIndex {
+    pub fn new(symbols: Vec<Symbol>) -> Self {
+        let mut index: HashMap<String, Vec<Symbol>> = HashMap::new();
+        for symbol in symbols {
+            index.entry(symbol.name.clone()).or_default().push(symbol);
+        }
+
+        // symbols are collected in the order their sources finish parsing.
+        for symbols in index.values_mut() {
+            symbols.sort_by(|a, b| (&a.origin, a.span).cmp(&(&b.origin, b.span)));
+        }
+
+        Self { symbols: index }
+    }
+
+    /// The number of distinct symbol names.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// The declarations of the given fully qualified name, more than one if it is
+    /// declared multiple times.
+    pub fn get(&self, name: &str) -> &[Symbol] {
+        self.symbols
+            .get(name.trim_start_matches('\\'))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.values().flatten()
+    }
+}
+
+impl Declaration {
+    pub(crate) fn into_symbol(self, origin: &str) -> Symbol {
+        Symbol {
+            name: self.name,
+            kind: self.kind,
+            origin: origin.to_string(),
+            span: self.span,
+        }
+    }
+}
+
+/// The symbols declared by the given tree.
+pub(crate) fn declarations(tree: &Tree) -> Vec<Declaration> {
+    let mut declarations = Vec::new();
+    for definition in &tree.definitions.definitions {
+        match definition {
+            Definition::Namespace(namespace) => {
+                let name = namespace.name.value.to_string();
+                for definition in &namespace.definitions {
+                    declare(&mut declarations, Some(&name), definition);
+                }
+            }
+            definition => declare(&mut declarations, None, definition),
+        }
+    }
+
+    declarations
+}
+
+fn declare(declarations: &mut Vec<Declaration>, namespace: Option<&str>, definition: &Definition) {
+    let (kind, name) = match definition {
+        Definition::Class(class) => (SymbolKind::Class, &class.name),
+        Definition::Interface(interface) => (SymbolKind::Interface, &interface.name),
+        Definition::Enum(r#enum) => match r#enum.as_ref() {
+            EnumDefinition::Backed(r#enum) => (SymbolKind::Enum, &r#enum.name),
+            EnumDefinition::Unit(r#enum) => (SymbolKind::Enum, &r#enum.name),
+        },
+        Definition::Function(function) => (SymbolKind::Function, &function.name),
+        Definition::Constant(constant) => (SymbolKind::Constant, &constant.name),
+        Definition::TypeAlias(alias) => (SymbolKind::TypeAlias, &alias.name.name),
+        Definition::Namespace(_) | Definition::Use(_) => return,
+    };
+
+    let name = match namespace {
+        Some(namespace) => format!("{}\\{}", namespace.trim_matches('\\'), name.value),
+        None => name.value.to_string(),
+    };
+
+    declarations.push(Declaration {
+        name,
+        kind,
+        span: (definition.initial_position(), definition.final_position()),
+    });
+}
@@ -1,18 +1,43 @@
+use bincode::config;
 use bincode::Decode;
 use bincode::Encode;
+use std::any::Any;
+use std::cell::RefCell;
+use std::error::Error as _;
 use std::fs;
-use std::fs::File;
-use std::io::Write;
+use std::io::ErrorKind;
+use std::panic;
+use std::panic::AssertUnwindSafe;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
 use ara_parser::tree::Tree;
 use ara_source::source::Source;
 use ara_source::source::SourceKind;
 
+use crate::cache;
+use crate::cache::Cache;
+use crate::cache::FileStamp;
+use crate::checksum;
+use crate::config::CacheKey;
+use crate::config::CachePolicy;
+use crate::config::CacheValidation;
 use crate::config::Config;
+use crate::config::LimitPolicy;
 use crate::error::Error;
-use crate::ARA_CACHED_SOURCE_EXTENSION;
+use crate::symbol;
+use crate::symbol::Declaration;
+use crate::symbol::SignedDeclarations;
+use crate::symbol::Symbol;
 use crate::ARA_DEFINITION_EXTENSION;
 
 #[derive(Debug, Hash, Encode, Decode)]
@@ -21,61 +46,477 @@ pub struct SignedTree {
     pub tree: Tree,
 }
 
-pub struct TreeBuilder<'a> {
+pub(crate) struct TreeBuilder<'a> {
     config: &'a Config,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    symbols: Option<Mutex<Vec<Symbol>>>,
+    cache_symbols: bool,
 }
 
 impl<'a> TreeBuilder<'a> {
     pub fn new(config: &'a Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            symbols: None,
+            cache_symbols: false,
+        }
+    }
+
+    /// Collect the symbols declared by each tree built by [`TreeBuilder::build_all`].
+    pub(crate) fn with_symbol_index(mut self) -> Self {
+        self.symbols = Some(Mutex::default());
+        self.cache_symbols = true;
+
+        self
+    }
+
+    /// Cache the symbols declared by each tree built by [`TreeBuilder::build_all`], without
+    /// collecting them.
+    pub(crate) fn with_symbol_cache(mut self) -> Self {
+        self.cache_symbols = true;
+
+        self
+    }
+
+    pub(crate) fn take_symbols(&self) -> Vec<Symbol> {
+        self.symbols
+            .as_ref()
+            .map(|symbols| std::mem::take(&mut *symbols.lock().unwrap()))
+            .unwrap_or_default()
+    }
+
+    /// The number of trees loaded from the cache, and the number of trees that had to be parsed.
+    pub(crate) fn cache_counters(&self) -> (usize, usize) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
     }
 
     pub fn build(&self, source_path: &Path) -> Result<(Source, Tree), Error> {
+        self.build_signed(source_path)
+            .map(|(source, tree, _)| (source, tree))
+    }
+
+    /// Build the tree of the given source, along with its signature, if the cache is used.
+    fn build_signed(&self, source_path: &Path) -> Result<(Source, Tree, Option<u64>), Error> {
+        let stamp = self.stamp(source_path);
         let source = self.build_source(source_path)?;
-        let tree = self.build_tree(&source)?;
+        let (tree, signature) = self.build_tree(&source, stamp)?;
+
+        Ok((source, tree, signature))
+    }
+
+    /// Build the sources claimed from the cursor with the given function, until they run
+    /// out, or the results are no longer wanted.
+    pub(crate) fn build_all<T, F>(
+        &self,
+        files: &[PathBuf],
+        cursor: &AtomicUsize,
+        build: fn(&Self, &Path) -> Result<T, Error>,
+        mut emit: F,
+    ) where
+        F: FnMut(usize, Result<T, Error>) -> bool,
+    {
+        loop {
+            let index = cursor.fetch_add(1, Ordering::Relaxed);
+            let source_path = match files.get(index) {
+                Some(source_path) => source_path,
+                None => break,
+            };
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| build(self, source_path)))
+                .unwrap_or_else(|payload| {
+                    let message = panic_message(payload);
+                    let origin = self.strip_root(source_path);
+
+                    log::error!("panicked while building source ({}): {}", origin, message);
+
+                    Err(Error::InternalError(origin, message))
+                });
+
+            if let Err(error @ (Error::SizeLimitError(..) | Error::TimeoutError(..))) = &result {
+                if self.config.limit_policy == LimitPolicy::Skip {
+                    log::warn!("skipping source: {}", error);
+
+                    continue;
+                }
+            }
+
+            if !emit(index, result) {
+                break;
+            }
+        }
+    }
+
+    /// Build the tree of the given source, caching, and collecting, its symbols if they
+    /// are indexed.
+    pub(crate) fn build_indexed(&self, source_path: &Path) -> Result<(Source, Tree), Error> {
+        let (source, tree, signature) = self.build_signed(source_path)?;
+        if self.cache_symbols {
+            let origin = source.origin.as_ref().unwrap();
+            let declarations = self.declarations(origin, signature, &tree)?;
+
+            if let Some(symbols) = &self.symbols {
+                symbols.lock().unwrap().extend(
+                    declarations
+                        .into_iter()
+                        .map(|declaration| declaration.into_symbol(origin)),
+                );
+            }
+        }
 
         Ok((source, tree))
     }
 
-    fn build_tree(&self, source: &Source) -> Result<Tree, Error> {
-        if self.config.cache.is_none() {
-            return ara_parser::parser::parse(source).map_err(Error::ParseError);
+    /// Make sure the tree of the given source is cached, without keeping it around.
+    ///
+    /// When the file metadata matches the cache index, and the entry is cached, the file
+    /// isn't read at all.
+    pub(crate) fn warm(&self, source_path: &Path) -> Result<(), Error> {
+        let origin = self.strip_root(source_path);
+        if let Some(signature) = self.indexed_signature(&origin, self.stamp(source_path))? {
+            let cache = Cache::new(self.config);
+            let cached = |paths: Vec<PathBuf>| paths.iter().any(|path| path.is_file());
+            if cached(cache.entry_lookup_paths(&origin, signature))
+                && (!self.cache_symbols || cached(cache.symbols_lookup_paths(&origin, signature)))
+            {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+
+                return Ok(());
+            }
+        }
+
+        self.build_indexed(source_path).map(|_| ())
+    }
+
+    fn build_tree(
+        &self,
+        source: &Source,
+        stamp: Option<FileStamp>,
+    ) -> Result<(Tree, Option<u64>), Error> {
+        if self.config.cache.is_none()
+            && self.config.cache_layers.is_empty()
+            && self.config.remote_cache.is_none()
+        {
+            return Ok((self.parse(source)?, None));
         }
 
-        let cached_file_path = self.get_cached_file_path(source);
-        let tree = self.get_from_cache(source, &cached_file_path).or_else(
-            |error| -> Result<Tree, Error> {
-                if let Error::DeserializeError(_) = error {
-                    log::error!(
-                        "error while deserializing cached file ({}) for source ({}): {}",
-                        self.strip_root(&cached_file_path),
+        let cache = Cache::new(self.config);
+        let signature = self.signature(source, stamp)?;
+        let writable = self.config.cache.is_some() && !self.config.cache_read_only;
+        let tree = match self.load_from_cache(&cache, source, signature)? {
+            Some(tree) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+
+                tree
+            }
+            None => match self.load_from_remote_cache(&cache, source, signature)? {
+                Some((tree, data)) => {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+
+                    log::info!(
+                        "loaded ({}) parsed source from remote cache.",
                         source.origin.as_ref().unwrap(),
-                        error
                     );
+
+                    if writable {
+                        let cached_file_path = cache.entry_path(source, signature);
+                        self.save_to_cache(source, &data, &cached_file_path)?;
+                    }
+
+                    tree
                 }
+                None => {
+                    let signed_tree = SignedTree::new(signature, self.parse(source)?);
+
+                    if writable || self.config.remote_cache.is_some() {
+                        match self
+                            .config
+                            .serializer
+                            .serialize(&signed_tree)
+                            .map(checksum::seal)
+                        {
+                            Ok(data) => {
+                                if writable {
+                                    let cached_file_path = cache.entry_path(source, signature);
+                                    self.save_to_cache(source, &data, &cached_file_path)?;
+                                }
+
+                                self.save_to_remote_cache(&cache, signature, &data)?;
+                            }
+                            Err(error) if writable => self.handle_cache_failure(
+                                Error::SerializeError(cache.entry_path(source, signature), error),
+                            )?,
+                            // without a cache directory, the entry is only meant for the remote cache.
+                            Err(error) => self.handle_cache_failure(Error::RemoteCacheError(
+                                cache.content_key(signature).to_string(),
+                                error,
+                            ))?,
+                        }
+                    }
 
-                let tree = ara_parser::parser::parse(source).map_err(Error::ParseError)?;
-                self.save_to_cache(source, tree, &cached_file_path)
+                    signed_tree.tree
+                }
             },
-        )?;
+        };
 
-        Ok(tree)
+        if writable
+            && (self.config.cache_key == CacheKey::Content
+                || self.config.cache_validation == CacheValidation::Metadata)
+        {
+            if let Err(error) = cache.update_index(source, signature, stamp) {
+                self.handle_cache_failure(error)?;
+            }
+        }
+
+        Ok((tree, Some(signature)))
+    }
+
+    /// The symbols declared by the given tree, cached next to its entry when the signature
+    /// of its source is known.
+    pub(crate) fn declarations(
+        &self,
+        origin: &str,
+        signature: Option<u64>,
+        tree: &Tree,
+    ) -> Result<Vec<Declaration>, Error> {
+        let declarations = symbol::declarations(tree);
+
+        if let Some(signature) = signature {
+            if self.config.cache.is_some() && !self.config.cache_read_only {
+                let cache = Cache::new(self.config);
+                let path = cache.symbols_path(origin, signature);
+                // under origin keys, the file could hold the symbols of an older signature.
+                let cached = read_declarations(&path);
+                if cached.map(|cached| cached.signature) != Some(signature) {
+                    let signed_declarations = SignedDeclarations {
+                        signature,
+                        declarations,
+                    };
+
+                    let result = bincode::encode_to_vec(&signed_declarations, config::standard())
+                        .map_err(|error| Error::SerializeError(path.clone(), error.into()))
+                        .and_then(|data| cache::write(&path, &checksum::seal(data)));
+
+                    if let Err(error) = result {
+                        self.handle_cache_failure(error)?;
+                    }
+
+                    return Ok(signed_declarations.declarations);
+                }
+            }
+        }
+
+        Ok(declarations)
+    }
+
+    /// The symbols declared by the given source, if they are cached.
+    pub(crate) fn get_declarations_from_cache(
+        &self,
+        origin: &str,
+        signature: u64,
+    ) -> Option<Vec<Declaration>> {
+        Cache::new(self.config)
+            .symbols_lookup_paths(origin, signature)
+            .into_iter()
+            .find_map(|path| {
+                let signed_declarations = read_declarations(&path)?;
+
+                (signed_declarations.signature == signature)
+                    .then_some(signed_declarations.declarations)
+            })
+    }
+
+    /// The origin, and signature, of the given source file, without parsing it.
+    ///
+    /// When the file metadata matches the cache index, the file isn't read at all.
+    pub(crate) fn sign(&self, source_path: &Path) -> Result<(String, u64), Error> {
+        let origin = self.strip_root(source_path);
+        if let Some(signature) = self.indexed_signature(&origin, self.stamp(source_path))? {
+            return Ok((origin, signature));
+        }
+
+        let source = self.build_source(source_path)?;
+        let signature = self.config.hasher.hash(&source.content);
+
+        Ok((origin, signature))
+    }
+
+    fn stamp(&self, source_path: &Path) -> Option<FileStamp> {
+        match self.config.cache_validation {
+            CacheValidation::Metadata => FileStamp::of(source_path),
+            CacheValidation::Content => None,
+        }
+    }
+
+    /// The signature of the given source, taken from the cache index if the file metadata
+    /// didn't change since it was recorded, saving from hashing the content.
+    fn signature(&self, source: &Source, stamp: Option<FileStamp>) -> Result<u64, Error> {
+        match self.indexed_signature(source.origin.as_ref().unwrap(), stamp)? {
+            Some(signature) => Ok(signature),
+            None => Ok(self.config.hasher.hash(&source.content)),
+        }
+    }
+
+    fn indexed_signature(
+        &self,
+        origin: &str,
+        stamp: Option<FileStamp>,
+    ) -> Result<Option<u64>, Error> {
+        if stamp.is_none() {
+            return Ok(None);
+        }
+
+        match Cache::new(self.config).read_index(origin) {
+            Ok(Some(entry)) if entry.stamp == stamp => Ok(Some(entry.signature)),
+            Ok(_) => Ok(None),
+            Err(error) => {
+                self.handle_cache_failure(error)?;
+
+                Ok(None)
+            }
+        }
+    }
+
+    fn load_from_cache(
+        &self,
+        cache: &Cache,
+        source: &Source,
+        signature: u64,
+    ) -> Result<Option<Tree>, Error> {
+        for cached_file_path in cache.lookup_paths(source, signature) {
+            match self.get_from_cache(source, signature, &cached_file_path) {
+                Ok(tree) => return Ok(Some(tree)),
+                Err(Error::CacheMiss(_)) => {}
+                Err(Error::IoError(_, error)) if error.kind() == ErrorKind::NotFound => {}
+                Err(error) => self.handle_cache_failure(error)?,
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn load_from_remote_cache(
+        &self,
+        cache: &Cache,
+        source: &Source,
+        signature: u64,
+    ) -> Result<Option<(Tree, Vec<u8>)>, Error> {
+        let remote_cache = match &self.config.remote_cache {
+            Some(remote_cache) => remote_cache,
+            None => return Ok(None),
+        };
+
+        let key = cache.content_key(signature).to_string();
+        let result = remote_cache.get(&key).and_then(|data| match data {
+            Some(data) => {
+                let payload = checksum::open(&data)
+                    .ok_or("the entry is corrupt, its checksum doesn't match its content")?;
+
+                Ok(Some((self.config.serializer.deserialize(payload)?, data)))
+            }
+            None => Ok(None),
+        });
+
+        match result {
+            Ok(Some((signed_tree, data))) if signed_tree.signature == signature => {
+                // remote entries are keyed by content, so the tree could have been cached
+                // for another origin.
+                let mut tree = signed_tree.tree;
+                tree.source = source.origin.clone().unwrap();
+
+                Ok(Some((tree, data)))
+            }
+            Ok(_) => Ok(None),
+            Err(error) => {
+                self.handle_cache_failure(Error::RemoteCacheError(key, error))?;
+
+                Ok(None)
+            }
+        }
+    }
+
+    fn save_to_remote_cache(
+        &self,
+        cache: &Cache,
+        signature: u64,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        if let Some(remote_cache) = &self.config.remote_cache {
+            let key = cache.content_key(signature).to_string();
+            if let Err(error) = remote_cache.put(&key, data) {
+                self.handle_cache_failure(Error::RemoteCacheError(key, error))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn handle_cache_failure(&self, error: Error) -> Result<(), Error> {
+        match self.config.cache_policy {
+            CachePolicy::Strict => return Err(error),
+            CachePolicy::Warn => match error.source() {
+                Some(cause) => log::warn!("{}, continuing without the cache: {}", error, cause),
+                None => log::warn!("{}, continuing without the cache.", error),
+            },
+            CachePolicy::Silent => {}
+        }
+
+        Ok(())
+    }
+
+    fn parse(&self, source: &Source) -> Result<Tree, Error> {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let timeout = match self.config.parse_timeout {
+            Some(timeout) => timeout,
+            None => return parse_source(source),
+        };
+
+        PARSE_THREAD.with(|parse_thread| {
+            let mut parse_thread = parse_thread.borrow_mut();
+            let result = parse_thread
+                .get_or_insert_with(ParseThread::spawn)
+                .parse(source, timeout);
+
+            if result.is_none() {
+                // the parser can't be interrupted, so the thread is left behind to finish
+                // on its own, and the next source is parsed on a new one.
+                *parse_thread = None;
+            }
+
+            result.unwrap_or_else(|| {
+                Err(Error::TimeoutError(source.origin.clone().unwrap(), timeout))
+            })
+        })
     }
 
-    fn get_from_cache(&self, source: &Source, cached_file_path: &PathBuf) -> Result<Tree, Error> {
+    pub(crate) fn get_from_cache(
+        &self,
+        source: &Source,
+        signature: u64,
+        cached_file_path: &PathBuf,
+    ) -> Result<Tree, Error> {
+        let data = fs::read(cached_file_path).map_err(Error::io(cached_file_path))?;
+        let payload =
+            checksum::open(&data).ok_or_else(|| Error::ChecksumError(cached_file_path.clone()))?;
         let signed_tree = self
             .config
             .serializer
-            .deserialize(&fs::read(cached_file_path)?)?;
+            .deserialize(payload)
+            .map_err(|error| Error::DeserializeError(cached_file_path.clone(), error))?;
 
-        let current_signature = self.config.hasher.hash(&source.content);
-        if signed_tree.signature != current_signature {
+        if signed_tree.signature != signature {
             log::warn!(
                 "cache miss due to source change ({}).",
                 source.origin.as_ref().unwrap(),
             );
 
-            return Err(Error::CacheMiss);
+            return Err(Error::CacheMiss(source.origin.clone().unwrap()));
         }
 
         log::info!(
@@ -84,57 +525,56 @@ impl<'a> TreeBuilder<'a> {
             self.strip_root(cached_file_path),
         );
 
-        Ok(signed_tree.tree)
+        // sources with the same content share an entry under content keys, so the tree
+        // could have been cached for another origin.
+        let mut tree = signed_tree.tree;
+        tree.source = source.origin.clone().unwrap();
+
+        Ok(tree)
     }
 
     fn save_to_cache(
         &self,
         source: &Source,
-        tree: Tree,
-        cached_file_path: &PathBuf,
-    ) -> Result<Tree, Error> {
-        let mut file = File::create(cached_file_path)?;
-        let signed_tree = SignedTree::new(self.config.hasher.hash(&source.content), tree);
-
-        let serialized = self.config.serializer.serialize(&signed_tree)?;
-        file.write_all(&serialized)?;
-
-        log::info!(
-            "saved ({}) parsed source to cache ({}).",
-            &signed_tree.tree.source,
-            self.strip_root(cached_file_path),
-        );
-
-        Ok(signed_tree.tree)
-    }
+        data: &[u8],
+        cached_file_path: &Path,
+    ) -> Result<(), Error> {
+        match cache::write(cached_file_path, data) {
+            Ok(()) => log::info!(
+                "saved ({}) parsed source to cache ({}).",
+                source.origin.as_ref().unwrap(),
+                self.strip_root(cached_file_path),
+            ),
+            Err(error) => self.handle_cache_failure(error)?,
+        }
 
-    fn get_cached_file_path(&self, source: &Source) -> PathBuf {
-        let cache_path = self.config.cache.as_ref().unwrap();
-        cache_path
-            .join(
-                self.config
-                    .hasher
-                    .hash(source.origin.as_ref().unwrap())
-                    .to_string(),
-            )
-            .with_extension(ARA_CACHED_SOURCE_EXTENSION)
+        Ok(())
     }
 
-    fn build_source(&self, source_path: &Path) -> Result<Source, Error> {
+    pub(crate) fn build_source(&self, source_path: &Path) -> Result<Source, Error> {
         let origin = self.strip_root(source_path);
-        let kind = match source_path.extension() {
-            Some(extension) if extension == ARA_DEFINITION_EXTENSION => SourceKind::Definition,
-            _ => SourceKind::Script,
+        let kind = if origin.ends_with(&format!(".{ARA_DEFINITION_EXTENSION}")) {
+            SourceKind::Definition
+        } else {
+            SourceKind::Script
         };
-        let content = fs::read_to_string(source_path)?;
+        let io = |error| Error::SourceIoError(origin.clone(), source_path.to_path_buf(), error);
+        if let Some(max_file_size) = self.config.max_file_size {
+            let size = fs::metadata(source_path).map_err(io)?.len();
+            if size > max_file_size {
+                return Err(Error::SizeLimitError(origin, size, max_file_size));
+            }
+        }
+
+        let content = fs::read_to_string(source_path).map_err(io)?;
 
         Ok(Source::new(kind, origin, content))
     }
 
     fn strip_root(&self, path: &Path) -> String {
         path.strip_prefix(&self.config.root)
-            .map(|path| path.to_string_lossy())
-            .unwrap()
+            .unwrap_or(path)
+            .to_string_lossy()
             .to_string()
     }
 }
@@ -144,3 +584,79 @@ impl SignedTree {
         Self { signature, tree }
     }
 }
+
+fn read_declarations(path: &Path) -> Option<SignedDeclarations> {
+    let data = fs::read(path).ok()?;
+    let (signed_declarations, _) =
+        bincode::decode_from_slice(checksum::open(&data)?, config::standard()).ok()?;
+
+    Some(signed_declarations)
+}
+
+thread_local! {
+    static PARSE_THREAD: RefCell<Option<ParseThread>> = const { RefCell::new(None) };
+}
+
+/// A thread that parses the sources of a worker, so the worker can stop waiting on a
+/// source that takes too long.
+struct ParseThread {
+    sources: Sender<Source>,
+    results: Receiver<Result<Tree, Error>>,
+}
+
+impl ParseThread {
+    fn spawn() -> Self {
+        let (sources, receiver) = mpsc::channel::<Source>();
+        let (sender, results) = mpsc::channel();
+        thread::spawn(move || {
+            for source in receiver {
+                if sender.send(parse_source(&source)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { sources, results }
+    }
+
+    /// Parse the given source, or return `None` if it takes longer than the timeout.
+    fn parse(&self, source: &Source, timeout: Duration) -> Option<Result<Tree, Error>> {
+        self.sources
+            .send(source.clone())
+            .expect("the parse thread exited early");
+
+        match self.results.recv_timeout(timeout) {
+            Ok(result) => Some(result),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => unreachable!("the parse thread exited early"),
+        }
+    }
+}
+
+/// Parse the given source, catching panics of the parser, so they can be told apart from
+/// panics of the forest itself.
+fn parse_source(source: &Source) -> Result<Tree, Error> {
+    match panic::catch_unwind(|| ara_parser::parser::parse(source)) {
+        Ok(result) => result.map_err(Error::ParseError),
+        Err(payload) => {
+            let message = panic_message(payload);
+            let origin = source.origin.clone().unwrap();
+
+            log::error!(
+                "the parser panicked while parsing source ({}): {}",
+                origin,
+                message
+            );
+
+            Err(Error::PanicError(origin, message))
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
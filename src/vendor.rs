@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::manifest::Manifest;
+use crate::manifest::ARA_MANIFEST_FILES;
+
+pub const COMPOSER_MANIFEST_FILE: &str = "composer.json";
+
+/// Collects the definition directories declared by the packages installed in the vendor directory.
+///
+/// A package declares its definitions either in its own `ara.toml` or `ara.json` manifest,
+/// or under the `extra.ara.definitions` key of its `composer.json`.
+pub struct VendorDefinitionsCollector<'a> {
+    config: &'a Config,
+}
+
+impl<'a> VendorDefinitionsCollector<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self { config }
+    }
+
+    pub fn collect(&self) -> Result<Vec<PathBuf>, Error> {
+        let vendor = match &self.config.vendor {
+            Some(vendor) => self.config.root.join(vendor),
+            None => return Ok(Vec::new()),
+        };
+
+        if !vendor.is_dir() {
+            return Err(Error::InvalidPath(vendor));
+        }
+
+        let mut definitions = Vec::new();
+        for package in self.packages(&vendor)? {
+            for definition in self.package_definitions(&package)? {
+                let definition = package.join(definition);
+                let definition = definition
+                    .strip_prefix(&self.config.root)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(definition);
+
+                log::debug!(
+                    "discovered definitions ({}) in package ({}).",
+                    definition.display(),
+                    package.display(),
+                );
+
+                definitions.push(definition);
+            }
+        }
+
+        Ok(definitions)
+    }
+
+    // packages are either installed directly in the vendor directory (`vendor/package`),
+    // or grouped by their vendor name, as composer does (`vendor/organization/package`).
+    fn packages(&self, vendor: &Path) -> Result<Vec<PathBuf>, Error> {
+        let mut packages = Vec::new();
+        for directory in sub_directories(vendor)? {
+            if is_package(&directory) {
+                packages.push(directory);
+
+                continue;
+            }
+
+            for directory in sub_directories(&directory)? {
+                if is_package(&directory) {
+                    packages.push(directory);
+                }
+            }
+        }
+
+        packages.sort();
+
+        Ok(packages)
+    }
+
+    fn package_definitions(&self, package: &Path) -> Result<Vec<String>, Error> {
+        if let Some(manifest) = ARA_MANIFEST_FILES
+            .iter()
+            .map(|name| package.join(name))
+            .find(|path| path.is_file())
+        {
+            return Ok(Manifest::from_file(&manifest)?.definitions);
+        }
+
+        let composer = package.join(COMPOSER_MANIFEST_FILE);
+        let content = fs::read_to_string(&composer).map_err(Error::io(&composer))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|error| Error::ManifestError(composer.clone(), error.to_string()))?;
+
+        match value.pointer("/extra/ara/definitions") {
+            None => Ok(Vec::new()),
+            Some(definitions) => serde_json::from_value(definitions.clone()).map_err(|error| {
+                Error::ManifestError(
+                    composer,
+                    format!("invalid value for `extra.ara.definitions`: {error}"),
+                )
+            }),
+        }
+    }
+}
+
+fn is_package(directory: &Path) -> bool {
+    ARA_MANIFEST_FILES
+        .iter()
+        .chain([COMPOSER_MANIFEST_FILE].iter())
+        .any(|name| directory.join(name).is_file())
+}
+
+fn sub_directories(directory: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut directories = Vec::new();
+    for entry in fs::read_dir(directory).map_err(Error::io(directory))? {
+        let path = entry.map_err(Error::io(directory))?.path();
+        if path.is_dir() {
+            directories.push(path);
+        }
+    }
+
+    Ok(directories)
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "async")]
+
+use std::env;
+use std::sync::Arc;
+
+use ara_forest::asynchronous::AsyncParser;
+use ara_forest::config::Config;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_parsing_project_a_asynchronously() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root).with_source("src").with_definitions(vec![
+        format!("vendor/std-bar/definitions"),
+        format!("vendor/std-foo/definitions"),
+    ]);
+
+    let forest = AsyncParser::new(Arc::new(config))
+        .unwrap()
+        .parse()
+        .await
+        .unwrap();
+
+    assert_eq!(forest.source.sources.len(), 6);
+    assert_eq!(forest.tree.trees.len(), 6);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_streaming_project_a_asynchronously() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root).with_source("src").with_threads(1);
+
+    let mut stream = AsyncParser::new(Arc::new(config)).unwrap().stream();
+    let mut origins = Vec::new();
+    while let Some(result) = stream.next().await {
+        let (source, tree) = result.unwrap();
+
+        assert_eq!(source.origin.as_ref(), Some(&tree.source));
+
+        origins.push(source.origin.unwrap());
+    }
+
+    assert_eq!(origins.len(), 4);
+    assert!(origins.contains(&"src/Foo/Bar/Baz/baz.ara".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_parsing_project_with_parse_error_asynchronously() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-b");
+
+    let config = Config::new(root).with_source("src");
+
+    let report = AsyncParser::new(Arc::new(config))
+        .unwrap()
+        .parse()
+        .await
+        .expect_err("Expected an error Report, but got a Forest object");
+
+    assert!(report
+        .issues
+        .first()
+        .unwrap()
+        .message
+        .contains("unexpected token `||`"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_indexing_symbols_asynchronously() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root).with_source("src").with_symbol_index(true);
+
+    let forest = AsyncParser::new(Arc::new(config))
+        .unwrap()
+        .parse()
+        .await
+        .unwrap();
+
+    let symbols = forest.symbols().unwrap();
+
+    assert_eq!(
+        symbols.get("Example\\list_directories")[0].origin,
+        "src/foo.ara"
+    );
+}
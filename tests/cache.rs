@@ -0,0 +1,415 @@
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use ara_forest::cache::Cache;
+use ara_forest::config::CacheKey;
+use ara_forest::config::CachePolicy;
+use ara_forest::config::CacheValidation;
+use ara_forest::config::Config;
+use ara_forest::error::Error;
+use ara_forest::Parser;
+
+mod common;
+
+use common::project_a;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+#[test]
+fn test_cache_stats_and_clear() {
+    let config = project_a("stats");
+    let cache = Cache::new(&config);
+
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    let stats = cache.stats().unwrap();
+
+    assert_eq!(stats.entries, 6);
+    assert!(stats.size > 0);
+
+    assert_eq!(cache.clear().unwrap(), 6);
+    assert_eq!(cache.stats().unwrap().entries, 0);
+}
+
+#[test]
+fn test_cache_prune() {
+    let config = project_a("prune");
+    let cache = Cache::new(&config);
+
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    let stale = config.cache.as_ref().unwrap().join("0.ara.cache");
+    fs::write(&stale, b"stale").unwrap();
+
+    assert_eq!(cache.stats().unwrap().entries, 7);
+    assert_eq!(cache.prune().unwrap(), 1);
+    assert_eq!(cache.stats().unwrap().entries, 6);
+    assert!(!stale.exists());
+}
+
+#[test]
+fn test_cache_prune_skips_sources_it_cannot_read() {
+    let config = project_a("prune-limit");
+    Cache::new(&config).clear().ok();
+
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    // the entry of a source that is now over the size limit is stale.
+    let config = config.with_max_file_size(1500);
+    let cache = Cache::new(&config);
+
+    assert_eq!(cache.prune().unwrap(), 1);
+    assert_eq!(cache.stats().unwrap().entries, 5);
+}
+
+#[test]
+fn test_cache_stats_without_a_cache_directory() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+    let config = Config::new(root).with_source("src");
+
+    assert_eq!(Cache::new(&config).stats().unwrap().entries, 0);
+}
+
+#[test]
+fn test_read_only_cache_is_never_written() {
+    let config = project_a("read-only").with_read_only_cache(true);
+    fs::remove_dir_all(config.cache.as_ref().unwrap()).ok();
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+
+    assert_eq!(forest.tree.trees.len(), 6);
+    assert!(!config.cache.as_ref().unwrap().exists());
+}
+
+#[test]
+fn test_read_only_cache_entries_are_never_removed() {
+    let config = project_a("read-only-entries");
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    let config = config.with_read_only_cache(true);
+    let cache = Cache::new(&config);
+    let archive = config.cache.as_ref().unwrap().with_extension("archive");
+    cache.export(&archive).unwrap();
+
+    assert!(matches!(cache.clear(), Err(Error::ConfigError(_))));
+    assert!(matches!(cache.prune(), Err(Error::ConfigError(_))));
+    assert!(matches!(cache.verify(true), Err(Error::ConfigError(_))));
+    assert!(matches!(cache.import(&archive), Err(Error::ConfigError(_))));
+
+    assert_eq!(cache.verify(false).unwrap().entries, 6);
+    assert_eq!(cache.stats().unwrap().entries, 6);
+}
+
+#[test]
+fn test_layered_cache() {
+    let base = project_a("layer-base");
+    fs::remove_dir_all(base.cache.as_ref().unwrap()).ok();
+    Parser::new(&base).unwrap().parse().unwrap();
+
+    let entries = Cache::new(&base).entries().unwrap();
+    fs::remove_file(&entries[0]).unwrap();
+
+    let config = project_a("layer-overlay").with_cache_layers(vec![".cache/layer-base"]);
+    let overlay = Cache::new(&config);
+    overlay.clear().ok();
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+
+    assert_eq!(forest.tree.trees.len(), 6);
+    assert_eq!(Cache::new(&base).stats().unwrap().entries, 5);
+    assert_eq!(
+        overlay.entries().unwrap(),
+        vec![config
+            .cache
+            .as_ref()
+            .unwrap()
+            .join(entries[0].file_name().unwrap())]
+    );
+}
+
+#[test]
+fn test_content_addressed_cache() {
+    let config = project_a("content").with_cache_key(CacheKey::Content);
+    let cache = Cache::new(&config);
+    fs::remove_dir_all(config.cache.as_ref().unwrap()).ok();
+
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    let index = cache.index().unwrap();
+    assert_eq!(index.len(), 6);
+    assert_eq!(index[0].origin, "src/Bar/bar.ara");
+
+    // entries are written to temporary files first, which are moved into place.
+    assert!(fs::read_dir(config.cache.as_ref().unwrap())
+        .unwrap()
+        .all(|entry| !entry.unwrap().path().to_string_lossy().ends_with(".tmp")));
+    for entry in &index {
+        let name = format!("{}.ara.cache", entry.key);
+        assert!(config.cache.as_ref().unwrap().join(name).is_file());
+    }
+
+    // moving the project root keeps the entries, as the content of the sources didn't change.
+    let moved = Config::new(format!("{MANIFEST_DIR}/tests/examples/project-a/src"))
+        .with_source(".")
+        .with_cache_directory(config.cache.as_ref().unwrap().to_string_lossy())
+        .with_cache_key(CacheKey::Content);
+
+    let forest = Parser::new(&moved).unwrap().parse().unwrap();
+    for (source, tree) in forest.iter() {
+        assert_eq!(&tree.source, source.origin.as_ref().unwrap());
+    }
+
+    assert_eq!(cache.stats().unwrap().entries, 6);
+    assert_eq!(cache.index().unwrap().len(), 10);
+
+    assert_eq!(cache.prune().unwrap(), 0);
+    assert_eq!(cache.index().unwrap().len(), 6);
+}
+
+#[test]
+fn test_metadata_cache_validation() {
+    let root = env::temp_dir().join("ara-forest-metadata-validation");
+    fs::remove_dir_all(&root).ok();
+    fs::create_dir_all(root.join("src")).unwrap();
+
+    // files modified within the last couple of seconds are always hashed.
+    let source = root.join("src/foo.ara");
+    let modified = SystemTime::now() - Duration::from_secs(3600);
+    let write = |content: &[u8]| {
+        fs::write(&source, content).unwrap();
+        File::options()
+            .write(true)
+            .open(&source)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    };
+
+    write(b"function foo(): void {}");
+
+    let config = Config::new(root.to_string_lossy())
+        .with_source("src")
+        .with_cache_directory(".cache")
+        .with_cache_validation(CacheValidation::Metadata);
+    let parser = Parser::new(&config).unwrap();
+
+    let (stats, _) = parser.warm_cache().unwrap();
+    assert_eq!((stats.hits, stats.misses), (0, 1));
+
+    let index = Cache::new(&config).index().unwrap();
+    assert_eq!(index.len(), 1);
+    assert!(index[0].stamp.is_some());
+
+    let (stats, _) = parser.warm_cache().unwrap();
+    assert_eq!((stats.hits, stats.misses), (1, 0));
+
+    // a change that keeps the size, and modification time, goes unnoticed, unless
+    // the content is hashed.
+    write(b"function bar(): void {}");
+
+    let (stats, _) = parser.warm_cache().unwrap();
+    assert_eq!((stats.hits, stats.misses), (1, 0));
+
+    // sources whose metadata didn't change aren't even read.
+    write(&[0xff; 23]);
+
+    let (stats, report) = parser.warm_cache().unwrap();
+    assert_eq!((stats.hits, stats.misses), (1, 0));
+    assert!(report.issues.is_empty());
+
+    write(b"function bar(): void {}");
+
+    let config = config.with_cache_validation(CacheValidation::Content);
+    let (stats, _) = Parser::new(&config).unwrap().warm_cache().unwrap();
+    assert_eq!((stats.hits, stats.misses), (0, 1));
+}
+
+#[test]
+fn test_cache_export_and_import() {
+    let exporter = project_a("export");
+    Parser::new(&exporter).unwrap().parse().unwrap();
+
+    let archive = exporter.cache.as_ref().unwrap().with_extension("archive");
+    assert_eq!(Cache::new(&exporter).export(&archive).unwrap(), 6);
+
+    let importer = project_a("import");
+    let cache = Cache::new(&importer);
+    cache.clear().ok();
+
+    let stats = cache.import(&archive).unwrap();
+    assert_eq!((stats.imported, stats.skipped), (6, 0));
+    assert_eq!(cache.stats().unwrap().entries, 6);
+
+    // sources that can't be found in the importing project are skipped.
+    let moved = Config::new(format!("{MANIFEST_DIR}/tests/examples/project-a/src"))
+        .with_source(".")
+        .with_cache_directory(format!(
+            "{MANIFEST_DIR}/tests/examples/project-a/.cache/import-moved"
+        ));
+    let stats = Cache::new(&moved).import(&archive).unwrap();
+    assert_eq!((stats.imported, stats.skipped), (0, 6));
+}
+
+#[test]
+fn test_cache_verify() {
+    let config = project_a("verify");
+    let cache = Cache::new(&config);
+    cache.clear().ok();
+
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    assert!(cache.verify(false).unwrap().corrupt.is_empty());
+
+    // flip a bit in the payload of an entry, which may still be decodable.
+    let entry = cache.entries().unwrap().remove(0);
+    let mut data = fs::read(&entry).unwrap();
+    let last = data.len() - 1;
+    data[last] ^= 1;
+    fs::write(&entry, data).unwrap();
+
+    let stats = cache.verify(false).unwrap();
+    assert_eq!(stats.entries, 6);
+    assert_eq!(stats.corrupt, vec![entry.clone()]);
+
+    let config = project_a("verify").with_cache_policy(CachePolicy::Strict);
+    let report = Parser::new(&config)
+        .unwrap()
+        .parse()
+        .expect_err("Expected an error Report, but got a Forest object");
+    assert_eq!(report.issues[0].code.as_deref(), Some("F0015"));
+
+    assert_eq!(cache.verify(true).unwrap().corrupt, vec![entry.clone()]);
+    assert!(!entry.exists());
+    assert_eq!(cache.verify(false).unwrap().entries, 5);
+}
+
+#[test]
+fn test_warming_the_cache() {
+    let config = project_a("warm");
+    let cache = Cache::new(&config);
+    cache.clear().ok();
+
+    let parser = Parser::new(&config).unwrap();
+    let (stats, report) = parser.warm_cache().unwrap();
+
+    assert_eq!((stats.files, stats.hits, stats.misses), (6, 0, 6));
+    assert_eq!(stats.failures, 0);
+    assert!(report.issues.is_empty());
+    assert_eq!(cache.stats().unwrap().entries, 6);
+
+    let (stats, _) = parser.warm_cache().unwrap();
+
+    assert_eq!((stats.files, stats.hits, stats.misses), (6, 6, 0));
+}
+
+#[test]
+fn test_warming_the_cache_of_a_project_with_parse_errors() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-b");
+    let config = Config::new(root)
+        .with_source("src")
+        .with_cache_directory(".cache/warm");
+    Cache::new(&config).clear().ok();
+
+    let (stats, report) = Parser::new(&config).unwrap().warm_cache().unwrap();
+
+    assert_eq!(stats.failures, 1);
+    assert_eq!(stats.misses, stats.files);
+    assert!(report.issues[0].message.contains("unexpected token `||`"));
+}
+
+#[test]
+fn test_warming_the_cache_requires_a_writable_cache() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+    let config = Config::new(root).with_source("src");
+
+    let report = Parser::new(&config)
+        .unwrap()
+        .warm_cache()
+        .expect_err("Expected an error Report, but got the warm stats");
+
+    assert_eq!(report.issues[0].code.as_deref(), Some("F0019"));
+
+    let config = project_a("warm-read-only").with_read_only_cache(true);
+
+    let report = Parser::new(&config)
+        .unwrap()
+        .warm_cache()
+        .expect_err("Expected an error Report, but got the warm stats");
+
+    assert_eq!(report.issues[0].code.as_deref(), Some("F0019"));
+}
+
+#[test]
+fn test_cache_export_skips_sources_it_cannot_read() {
+    let config = project_a("export-limit");
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    let config = config.with_max_file_size(1500);
+    let archive = config.cache.as_ref().unwrap().with_extension("archive");
+
+    assert_eq!(Cache::new(&config).export(&archive).unwrap(), 5);
+}
+
+#[test]
+fn test_cache_failure_policies() {
+    let config = project_a("policy");
+    let cache = Cache::new(&config);
+    fs::remove_dir_all(config.cache.as_ref().unwrap()).ok();
+
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    // replacing an entry with a directory makes both reading and writing it fail.
+    let entry = cache.entries().unwrap().remove(0);
+    fs::remove_file(&entry).unwrap();
+    fs::create_dir(&entry).unwrap();
+
+    let config = config.with_cache_policy(CachePolicy::Strict);
+    let report = Parser::new(&config)
+        .unwrap()
+        .parse()
+        .expect_err("Expected an error Report, but got a Forest object");
+
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].code.as_deref(), Some("F0005"));
+
+    for policy in [CachePolicy::Warn, CachePolicy::Silent] {
+        let config = project_a("policy").with_cache_policy(policy);
+        let forest = Parser::new(&config).unwrap().parse().unwrap();
+
+        assert_eq!(forest.tree.trees.len(), 6);
+    }
+
+    fs::remove_dir(&entry).unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_serializer_cache_round_trip() {
+    use ara_forest::serializer::JsonSerializer;
+
+    let config = project_a("json").with_serializer(JsonSerializer::new());
+    let cache = Cache::new(&config);
+    cache.clear().ok();
+
+    let parser = Parser::new(&config).unwrap();
+    let first = parser.parse().unwrap();
+    let second = parser.parse().unwrap();
+
+    assert_eq!(cache.stats().unwrap().entries, 6);
+    assert_eq!(first.tree.trees.len(), second.tree.trees.len());
+
+    let entry = fs::read_dir(config.cache.as_ref().unwrap())
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    // entries start with an 8 bytes header, holding the checksum of the serialized tree.
+    let json: serde_json::Value =
+        serde_json::from_slice(&fs::read(entry.path()).unwrap()[8..]).unwrap();
+
+    assert!(json["signature"].is_u64());
+    assert!(json["tree"]["definitions"].is_object());
+}
@@ -0,0 +1,149 @@
+#![cfg(feature = "cli")]
+
+use std::env;
+use std::process::Command;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+const BINARY: &str = env!("CARGO_BIN_EXE_ara-forest");
+
+#[test]
+fn test_listing_files_as_json() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let output = Command::new(BINARY)
+        .args(["list-files", "--format", "json", "--root", &root])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let files: Vec<String> = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(files.len(), 6);
+    assert!(files.contains(&"src/foo.ara".to_string()));
+}
+
+#[test]
+fn test_rejecting_formats_a_command_does_not_support() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    for command in [vec!["list-files"], vec!["cache", "stats"]] {
+        let output = Command::new(BINARY)
+            .args(command)
+            .args(["--format", "sarif", "--root", &root])
+            .output()
+            .unwrap();
+
+        assert!(!output.status.success());
+    }
+}
+
+#[test]
+fn test_checking_a_valid_project() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a/src/Foo");
+
+    let output = Command::new(BINARY)
+        .args(["check", "--root", &root, "--cache-dir", ".cache/cli"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_checking_an_invalid_configuration() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let output = Command::new(BINARY)
+        .args([
+            "check",
+            "--root",
+            &root,
+            "--threads",
+            "0",
+            "--format",
+            "json",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("threads must be greater than zero"));
+}
+
+#[test]
+fn test_parsing_outside_of_a_project() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-empty");
+
+    let output = Command::new(BINARY)
+        .args(["parse", "--root", &root])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_dumping_a_tree() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let output = Command::new(BINARY)
+        .args(["dump", "src/foo.ara", "--cache-dir", ".cache/cli-dump"])
+        .current_dir(&root)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let tree: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(tree["source"], "src/foo.ara");
+    assert!(tree["definitions"]["definitions"].is_array());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_checking_with_non_unicode_environment_variables() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a/src/Foo");
+    let invalid = OsStr::from_bytes(b"\xff");
+
+    let output = Command::new(BINARY)
+        .args(["check", "--root", &root])
+        .env("UNRELATED", invalid)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let output = Command::new(BINARY)
+        .args(["check", "--root", &root, "--format", "json"])
+        .env("ARA_FOREST_LOG", invalid)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("F0013"));
+}
+
+#[test]
+fn test_clearing_a_read_only_cache() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let output = Command::new(BINARY)
+        .args([
+            "cache",
+            "clear",
+            "--root",
+            &root,
+            "--cache-dir",
+            ".cache/cli",
+        ])
+        .env("ARA_FOREST_CACHE_READ_ONLY", "true")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+}
@@ -0,0 +1,15 @@
+use ara_forest::config::Config;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+pub fn project_a(cache: &str) -> Config {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    Config::new(root)
+        .with_source("src")
+        .with_definitions(vec![
+            format!("vendor/std-bar/definitions"),
+            format!("vendor/std-foo/definitions"),
+        ])
+        .with_cache_directory(format!(".cache/{cache}"))
+}
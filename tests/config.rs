@@ -0,0 +1,208 @@
+use std::env;
+use std::path::PathBuf;
+
+use ara_forest::config::Config;
+use ara_forest::error::Error;
+use ara_forest::manifest::Manifest;
+use ara_forest::Parser;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+#[test]
+fn test_loading_config_from_a_toml_manifest() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::from_file(format!("{root}/ara.toml")).unwrap();
+
+    assert_eq!(config.root, PathBuf::from(&root));
+    assert_eq!(config.source, PathBuf::from("src"));
+    assert_eq!(
+        config.definitions,
+        vec![
+            PathBuf::from("vendor/std-bar/definitions"),
+            PathBuf::from("vendor/std-foo/definitions"),
+        ]
+    );
+    assert_eq!(config.cache, Some(PathBuf::from(&root).join(".cache")));
+    assert_eq!(config.threads, 2);
+    assert!(config.logger.is_some());
+}
+
+#[test]
+fn test_discovering_a_json_manifest() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-b");
+
+    let config = Config::discover(&root).unwrap();
+
+    assert_eq!(config.source, PathBuf::from("src"));
+    assert_eq!(config.excludes, vec![PathBuf::from("src/Bar")]);
+
+    // the file with a parse error is excluded by the manifest.
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+
+    assert_eq!(forest.source.sources.len(), 1);
+}
+
+#[test]
+fn test_discovering_a_manifest_in_a_project_without_one() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-empty");
+
+    let result = Config::discover(root);
+
+    assert!(
+        matches!(result, Err(Error::ManifestNotFound(_))),
+        "Expected a ManifestNotFound error, but got something else",
+    );
+}
+
+#[test]
+fn test_loading_an_invalid_manifest() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-invalid-manifest");
+
+    let error = Config::discover(root)
+        .err()
+        .expect("Expected a ManifestError, but got a Config object");
+
+    assert!(matches!(error, Error::ManifestError(_, _)));
+    assert!(error.to_string().contains("`logger.level`"));
+    assert!(error.to_string().contains("unknown log level `verbose`"));
+}
+
+#[test]
+fn test_loading_a_manifest_with_an_unknown_key() {
+    let error = Manifest::from_json(r#"{ "sources": "src" }"#)
+        .expect_err("Expected an error, but got a Manifest object");
+
+    assert!(error.contains("unknown field `sources`"));
+}
+
+#[test]
+fn test_discovering_a_manifest_from_a_nested_directory() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::discover_from(format!("{root}/src/Foo/Bar")).unwrap();
+
+    assert_eq!(config.root, PathBuf::from(&root));
+    assert_eq!(config.source, PathBuf::from("src"));
+}
+
+#[test]
+fn test_discovering_a_manifest_from_a_nested_file() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-b");
+
+    let config = Config::discover_from(format!("{root}/src/foo.ara")).unwrap();
+
+    assert_eq!(config.root, PathBuf::from(&root));
+}
+
+#[test]
+fn test_discovering_a_manifest_from_outside_of_a_project() {
+    let directory = format!("{MANIFEST_DIR}/tests/examples/project-empty/src");
+
+    let result = Config::discover_from(directory);
+
+    assert!(
+        matches!(result, Err(Error::ProjectNotFound(_))),
+        "Expected a ProjectNotFound error, but got something else",
+    );
+}
+
+#[test]
+fn test_overriding_config_from_environment_variables() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::discover(&root)
+        .unwrap()
+        .with_overrides(vec![
+            ("ARA_FOREST_THREADS", "7"),
+            ("ARA_FOREST_CACHE_DIR", "/tmp/ara-forest-cache"),
+            ("ARA_FOREST_LOG", "trace"),
+            ("ARA_FOREST_CACHE_READ_ONLY", "true"),
+            ("PATH", "/usr/bin"),
+        ])
+        .unwrap();
+
+    assert_eq!(config.source, PathBuf::from("src"));
+    assert_eq!(config.threads, 7);
+    assert_eq!(config.cache, Some(PathBuf::from("/tmp/ara-forest-cache")));
+    assert!(config.logger.is_some());
+    assert!(config.cache_read_only);
+
+    // explicit builders take precedence over the environment.
+    let config = config.with_threads(3);
+
+    assert_eq!(config.threads, 3);
+}
+
+#[test]
+fn test_overriding_config_from_an_invalid_environment_variable() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let error = Config::new(root)
+        .with_overrides(vec![("ARA_FOREST_THREADS", "many")])
+        .err()
+        .expect("Expected an EnvironmentError, but got a Config object");
+
+    assert!(matches!(error, Error::EnvironmentError(name, _) if name == "ARA_FOREST_THREADS"));
+}
+
+#[cfg(not(feature = "remote"))]
+#[test]
+fn test_overriding_the_remote_cache_without_the_remote_feature() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let error = Config::new(root)
+        .with_overrides(vec![("ARA_FOREST_REMOTE_CACHE", "http://127.0.0.1:9")])
+        .err()
+        .expect("Expected an EnvironmentError, but got a Config object");
+
+    assert!(matches!(error, Error::EnvironmentError(name, _) if name == "ARA_FOREST_REMOTE_CACHE"));
+}
+
+#[test]
+fn test_validating_a_valid_config() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::discover(root).unwrap();
+
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_validating_an_invalid_config_reports_every_issue() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(&root)
+        .with_source("src")
+        .with_definitions(vec!["src/Foo", "vendor/std-baz/definitions"])
+        .with_cache_directory("src/.cache")
+        .with_threads(0);
+
+    let report = Parser::new(&config)
+        .err()
+        .expect("Expected an error Report, but got a Parser object");
+
+    let messages = report
+        .issues
+        .iter()
+        .map(|issue| issue.message.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(messages.len(), 4);
+    assert!(messages[0].contains("threads must be greater than zero"));
+    assert!(messages[1].contains("src/Foo overlap with the source"));
+    assert!(messages[2].contains("vendor/std-baz/definitions does not exist"));
+    assert!(messages[3].contains("src/.cache must not be inside of"));
+}
+
+#[test]
+fn test_validating_a_config_with_a_missing_root() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-fake");
+
+    let report = Config::new(root).with_source("src").validate().unwrap_err();
+
+    assert_eq!(report.issues.len(), 2);
+    assert!(report.issues[0]
+        .message
+        .contains("project-fake does not exist"));
+}
@@ -0,0 +1,83 @@
+use std::env;
+use std::error::Error as StdError;
+use std::fs;
+use std::path::Path;
+
+use ara_forest::config::Config;
+use ara_forest::error::Error;
+use ara_forest::Parser;
+use ara_reporting::Report;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+#[test]
+fn test_io_error_carries_the_path_and_its_cause() {
+    let path = format!("{MANIFEST_DIR}/tests/examples/missing/ara.toml");
+
+    let error = Config::from_file(&path)
+        .err()
+        .expect("Expected an IoError, but got a Config object");
+
+    assert!(matches!(&error, Error::IoError(..)));
+    assert_eq!(error.path(), Some(Path::new(&path)));
+    assert_eq!(error.code(), Some("F0005"));
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn test_error_into_report() {
+    let path = format!("{MANIFEST_DIR}/tests/examples/missing/ara.toml");
+    let error = Config::from_file(&path)
+        .err()
+        .expect("Expected an IoError, but got a Config object");
+    let cause = error.source().unwrap().to_string();
+
+    let report = Box::<Report>::from(error);
+
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].code.as_deref(), Some("F0005"));
+    assert!(report.issues[0].message.contains(&path));
+    assert_eq!(report.issues[0].notes, vec![format!("caused by: {cause}")]);
+}
+
+#[test]
+fn test_panic_error_into_report() {
+    let error = Error::PanicError("src/foo.ara".to_string(), "oops".to_string());
+
+    assert_eq!(error.origin(), Some("src/foo.ara"));
+
+    let report = Box::<Report>::from(error);
+
+    assert_eq!(report.issues[0].code.as_deref(), Some("F0007"));
+    assert_eq!(
+        report.issues[0].source,
+        Some(("src/foo.ara".to_string(), 0, 0))
+    );
+}
+
+#[test]
+fn test_source_io_error_carries_the_origin() {
+    let root = env::temp_dir().join("ara-forest-source-io-error");
+    fs::remove_dir_all(&root).ok();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/foo.ara"), "function foo(): void {}").unwrap();
+
+    let config = Config::new(root.to_string_lossy()).with_source("src");
+    let forest = Parser::new(&config).unwrap().parse_lazy().unwrap();
+
+    fs::remove_file(root.join("src/foo.ara")).unwrap();
+
+    let error = forest.source("src/foo.ara").unwrap_err();
+
+    assert!(matches!(&error, Error::SourceIoError(..)));
+    assert_eq!(error.origin(), Some("src/foo.ara"));
+    assert_eq!(error.path(), Some(root.join("src/foo.ara").as_path()));
+
+    let report = Box::<Report>::from(error);
+
+    assert_eq!(report.issues[0].code.as_deref(), Some("F0018"));
+    assert_eq!(
+        report.issues[0].source,
+        Some(("src/foo.ara".to_string(), 0, 0))
+    );
+}
@@ -0,0 +1,93 @@
+use std::env;
+
+use ara_forest::config::Config;
+use ara_forest::export::ExportFormat;
+use ara_forest::export::ReportExporter;
+use ara_forest::Parser;
+use ara_reporting::Report;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+fn parse_project_b() -> (ReportExporter, Box<Report>) {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-b");
+    let config = Config::new(root).with_source("src");
+
+    let report = Parser::new(&config)
+        .unwrap()
+        .parse()
+        .expect_err("Expected an error Report, but got a Forest object");
+
+    (ReportExporter::from_config(&config).unwrap(), report)
+}
+
+#[test]
+fn test_exporting_diagnostics() {
+    let (exporter, report) = parse_project_b();
+
+    let diagnostics = exporter.diagnostics(&report);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].file.as_deref(), Some("src/Bar/bar.ara"));
+    assert_eq!(diagnostics[0].line, Some(1));
+    assert_eq!(diagnostics[0].column, Some(31));
+    assert_eq!(diagnostics[0].end_column, Some(33));
+    assert_eq!(diagnostics[0].severity, "error");
+    assert_eq!(diagnostics[0].code.as_deref(), Some("P0011"));
+}
+
+#[test]
+fn test_exporting_json() {
+    let (exporter, report) = parse_project_b();
+
+    let json: serde_json::Value =
+        serde_json::from_str(&exporter.export(&report, ExportFormat::Json)).unwrap();
+
+    assert_eq!(json[0]["file"], "src/Bar/bar.ara");
+    assert_eq!(json[0]["line"], 1);
+    assert_eq!(json[0]["column"], 31);
+}
+
+#[test]
+fn test_exporting_sarif() {
+    let (exporter, report) = parse_project_b();
+
+    let sarif: serde_json::Value =
+        serde_json::from_str(&exporter.export(&report, ExportFormat::Sarif)).unwrap();
+
+    assert_eq!(sarif["version"], "2.1.0");
+
+    let result = &sarif["runs"][0]["results"][0];
+
+    assert_eq!(result["ruleId"], "P0011");
+    assert_eq!(result["level"], "error");
+
+    let location = &result["locations"][0]["physicalLocation"];
+
+    assert_eq!(location["artifactLocation"]["uri"], "src/Bar/bar.ara");
+    assert_eq!(location["region"]["startLine"], 1);
+    assert_eq!(location["region"]["startColumn"], 31);
+}
+
+#[test]
+fn test_exporting_checkstyle() {
+    let (exporter, report) = parse_project_b();
+
+    let xml = exporter.export(&report, ExportFormat::Checkstyle);
+
+    assert!(xml.contains("<file name=\"src/Bar/bar.ara\">"));
+    assert!(xml.contains("<file name=\"src/foo.ara\">"));
+    assert!(xml.contains(
+        "<error line=\"1\" column=\"31\" severity=\"error\" message=\"unexpected token `||`, expected `;`\" source=\"P0011\"/>"
+    ));
+}
+
+#[test]
+fn test_exporting_junit() {
+    let (exporter, report) = parse_project_b();
+
+    let xml = exporter.export(&report, ExportFormat::JUnit);
+
+    assert!(xml.contains("<testsuite name=\"ara-forest\" tests=\"2\" failures=\"1\">"));
+    assert!(xml.contains("<testcase name=\"src/foo.ara\" classname=\"ara-forest\"/>"));
+    assert!(xml.contains("<failure type=\"P0011\""));
+}
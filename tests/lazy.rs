@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use ara_forest::cache::Cache;
+use ara_forest::error::Error;
+use ara_forest::Parser;
+
+mod common;
+
+use common::project_a;
+
+#[test]
+fn test_lazy_forest_loads_trees_on_demand() {
+    let config = project_a("lazy");
+    Cache::new(&config).clear().ok();
+
+    let parser = Parser::new(&config).unwrap();
+    let forest = parser.parse().unwrap();
+    let lazy = parser.parse_lazy().unwrap();
+
+    assert_eq!(lazy.len(), 6);
+    for (entry, source) in lazy.entries().iter().zip(&forest.source.sources) {
+        assert_eq!(Some(&entry.origin), source.origin.as_ref());
+        assert!(!lazy.is_loaded(&entry.origin));
+    }
+
+    let tree = lazy.get("src/Bar/bar.ara").unwrap();
+    let position = lazy
+        .entries()
+        .iter()
+        .position(|entry| entry.origin == "src/Bar/bar.ara")
+        .unwrap();
+
+    assert!(lazy.is_loaded("src/Bar/bar.ara"));
+    assert!(Arc::ptr_eq(&tree, &lazy.get("src/Bar/bar.ara").unwrap()));
+    assert_eq!(
+        format!("{:?}", tree),
+        format!("{:?}", forest.tree.trees[position])
+    );
+
+    lazy.unload();
+
+    assert!(!lazy.is_loaded("src/Bar/bar.ara"));
+}
+
+#[test]
+fn test_lazy_forest_memory_limit() {
+    let config = project_a("lazy-limit");
+    let lazy = Parser::new(&config)
+        .unwrap()
+        .parse_lazy()
+        .unwrap()
+        .with_memory_limit(1000);
+
+    // the sources are 1424, 268, and 399 bytes long.
+    lazy.get("src/Foo/Bar/bar.ara").unwrap();
+    assert!(lazy.is_loaded("src/Foo/Bar/bar.ara"));
+
+    lazy.get("src/Bar/bar.ara").unwrap();
+    lazy.get("src/foo.ara").unwrap();
+
+    assert!(!lazy.is_loaded("src/Foo/Bar/bar.ara"));
+    assert!(lazy.is_loaded("src/Bar/bar.ara"));
+    assert!(lazy.is_loaded("src/foo.ara"));
+}
+
+#[test]
+fn test_lazy_forest_unknown_source() {
+    let config = project_a("lazy-unknown");
+    let lazy = Parser::new(&config).unwrap().parse_lazy().unwrap();
+
+    let error = lazy
+        .get("src/missing.ara")
+        .expect_err("Expected a SourceNotFound error, but got a tree");
+
+    assert!(matches!(error, Error::SourceNotFound(_)));
+    assert_eq!(error.code(), Some("F0016"));
+    assert_eq!(lazy.signature("src/missing.ara"), None);
+}
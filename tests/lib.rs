@@ -1,8 +1,11 @@
 use std::env;
+use std::time::Duration;
 
 use ara_forest::config::Config;
+use ara_forest::config::LimitPolicy;
 use ara_forest::logger::{LogLevel, Logger};
 use ara_forest::Parser;
+use ara_source::source::SourceKind;
 
 const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
 
@@ -19,10 +22,16 @@ fn test_parsing_project_a_with_log() {
         .with_cache_directory(".cache")
         .with_logger(Logger::new().with_level(LogLevel::Debug));
 
-    let forest = Parser::new(&config).parse().unwrap();
+    // the logger is only installed once, however many times the project is parsed.
+    let parser = Parser::new(&config).unwrap();
+    for _ in 0..2 {
+        let forest = parser.parse().unwrap();
 
-    assert_eq!(forest.source.sources.len(), 6);
-    assert_eq!(forest.tree.trees.len(), 6);
+        assert_eq!(forest.source.sources.len(), 6);
+        assert_eq!(forest.tree.trees.len(), 6);
+    }
+
+    parser.warm_cache().unwrap();
 }
 
 #[test]
@@ -31,12 +40,33 @@ fn test_parsing_empty_project() {
 
     let config = Config::new(root).with_source("src");
 
-    let forest = Parser::new(&config).parse().unwrap();
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
 
     assert!(forest.source.sources.is_empty());
     assert!(forest.tree.trees.is_empty());
 }
 
+#[test]
+fn test_definition_sources_are_detected_by_their_extension() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root).with_source("src").with_definitions(vec![
+        format!("vendor/std-bar/definitions"),
+        format!("vendor/std-foo/definitions"),
+    ]);
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+
+    for source in &forest.source.sources {
+        let origin = source.origin.as_ref().unwrap();
+        if origin.ends_with(".d.ara") {
+            assert_eq!(source.kind, SourceKind::Definition, "{origin}");
+        } else {
+            assert_eq!(source.kind, SourceKind::Script, "{origin}");
+        }
+    }
+}
+
 #[test]
 fn test_parsing_project_with_parse_error() {
     let root = format!("{MANIFEST_DIR}/tests/examples/project-b");
@@ -44,6 +74,7 @@ fn test_parsing_project_with_parse_error() {
     let config = Config::new(root).with_source("src");
 
     let report = Parser::new(&config)
+        .unwrap()
         .parse()
         .expect_err("Expected an error Report, but got a Forest object");
 
@@ -54,3 +85,263 @@ fn test_parsing_project_with_parse_error() {
         .message
         .contains("unexpected token `||`"));
 }
+
+#[test]
+fn test_parsing_project_a_as_a_stream() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root).with_source("src").with_definitions(vec![
+        format!("vendor/std-bar/definitions"),
+        format!("vendor/std-foo/definitions"),
+    ]);
+
+    let origins = Parser::new(&config)
+        .unwrap()
+        .parse_iter(|trees| {
+            trees
+                .map(|result| result.unwrap().0.origin.unwrap())
+                .collect::<Vec<String>>()
+        })
+        .unwrap();
+
+    assert_eq!(origins.len(), 6);
+    assert!(origins.contains(&"src/foo.ara".to_string()));
+    assert!(origins.contains(&"vendor/std-foo/definitions/std-foo.d.ara".to_string()));
+}
+
+#[test]
+fn test_stopping_a_stream_early() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root).with_source("src").with_threads(1);
+
+    let first = Parser::new(&config)
+        .unwrap()
+        .parse_iter(|mut trees| trees.next())
+        .unwrap();
+
+    assert!(first.unwrap().is_ok());
+}
+
+#[test]
+fn test_a_panicking_worker_does_not_stop_the_other_sources() {
+    use ara_forest::error::BoxedError;
+    use ara_forest::serializer::BincodeSerializer;
+    use ara_forest::serializer::Serializer;
+    use ara_forest::tree::SignedTree;
+    use ara_reporting::Report;
+
+    struct PanickingSerializer(BincodeSerializer);
+
+    impl Serializer for PanickingSerializer {
+        fn serialize(&self, signed_tree: &SignedTree) -> Result<Vec<u8>, BoxedError> {
+            if signed_tree.tree.source == "src/foo.ara" {
+                panic!("failed to serialize src/foo.ara");
+            }
+
+            self.0.serialize(signed_tree)
+        }
+
+        fn deserialize(&self, data: &[u8]) -> Result<SignedTree, BoxedError> {
+            self.0.deserialize(data)
+        }
+    }
+
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root)
+        .with_source("src")
+        .with_definitions(vec![
+            format!("vendor/std-bar/definitions"),
+            format!("vendor/std-foo/definitions"),
+        ])
+        .with_cache_directory(".cache/panic")
+        .with_serializer(PanickingSerializer(BincodeSerializer::new()));
+
+    let (trees, errors): (Vec<_>, Vec<_>) = Parser::new(&config)
+        .unwrap()
+        .parse_iter(|trees| trees.collect::<Vec<_>>())
+        .unwrap()
+        .into_iter()
+        .partition(Result::is_ok);
+
+    assert_eq!(trees.len(), 5);
+    assert_eq!(errors.len(), 1);
+
+    let error = errors.into_iter().next().unwrap().unwrap_err();
+    assert_eq!(error.origin(), Some("src/foo.ara"));
+
+    let report = Box::<Report>::from(error);
+    assert_eq!(report.issues[0].code.as_deref(), Some("F0017"));
+    assert!(!report.issues[0]
+        .notes
+        .iter()
+        .any(|note| note.contains("ara-lang/ara")));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parsing_project_a_using_a_rayon_pool() {
+    use std::sync::Arc;
+
+    use ara_forest::executor::RayonExecutor;
+
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+    let pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap(),
+    );
+
+    let config = Config::new(root)
+        .with_source("src")
+        .with_threads(2)
+        .with_executor(RayonExecutor::new(pool));
+
+    let parser = Parser::new(&config).unwrap();
+    for _ in 0..3 {
+        let forest = parser.parse().unwrap();
+
+        assert_eq!(forest.source.sources.len(), 4);
+        assert_eq!(forest.tree.trees.len(), 4);
+    }
+}
+
+#[test]
+fn test_reporting_sources_exceeding_the_maximum_file_size() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root)
+        .with_source("src")
+        .with_max_file_size(1024);
+
+    let report = Parser::new(&config)
+        .unwrap()
+        .parse()
+        .expect_err("Expected an error Report, but got a Forest object");
+
+    assert_eq!(report.issues.len(), 1);
+    assert!(report.issues[0].message.contains("src/Foo/Bar/bar.ara"));
+    assert_eq!(report.issues[0].code.as_deref(), Some("F0008"));
+    assert_eq!(
+        report.issues[0].source,
+        Some(("src/Foo/Bar/bar.ara".to_string(), 0, 0))
+    );
+}
+
+#[test]
+fn test_skipping_sources_exceeding_the_maximum_file_size() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root)
+        .with_source("src")
+        .with_max_file_size(1024)
+        .with_limit_policy(LimitPolicy::Skip);
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+
+    assert_eq!(forest.source.sources.len(), 3);
+    assert_eq!(forest.tree.trees.len(), 3);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parsing_project_a_from_inside_a_rayon_pool() {
+    use std::sync::Arc;
+
+    use ara_forest::executor::RayonExecutor;
+
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+    let pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap(),
+    );
+
+    let config = Config::new(root)
+        .with_source("src")
+        .with_executor(RayonExecutor::new(pool.clone()));
+
+    let forest = pool
+        .install(|| Parser::new(&config).unwrap().parse())
+        .unwrap();
+
+    assert_eq!(forest.len(), 4);
+}
+
+#[test]
+fn test_parsing_project_a_with_a_parse_timeout() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root)
+        .with_source("src")
+        .with_parse_timeout(Duration::from_secs(60));
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+
+    assert_eq!(forest.tree.trees.len(), 4);
+}
+
+#[test]
+fn test_skipping_sources_that_time_out() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    // every source times out, so each one is parsed on a new thread.
+    let config = Config::new(root)
+        .with_source("src")
+        .with_threads(1)
+        .with_parse_timeout(Duration::ZERO)
+        .with_limit_policy(LimitPolicy::Skip);
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+
+    assert!(forest.is_empty());
+}
+
+#[test]
+fn test_looking_up_sources_and_trees_by_origin() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+
+    let config = Config::new(root).with_source("src").with_definitions(vec![
+        format!("vendor/std-bar/definitions"),
+        format!("vendor/std-foo/definitions"),
+    ]);
+
+    let mut forest = Parser::new(&config).unwrap().parse().unwrap();
+
+    let (source, tree) = forest.get("src/Foo/Bar/bar.ara").unwrap();
+    assert_eq!(source.origin.as_deref(), Some("src/Foo/Bar/bar.ara"));
+    assert!(!tree.definitions.definitions.is_empty());
+
+    assert!(forest.contains("vendor/std-foo/definitions/std-foo.d.ara"));
+    assert!(forest.get("src/missing.ara").is_none());
+
+    assert_eq!(forest.len(), 6);
+    assert_eq!(forest.iter().count(), 6);
+    assert_eq!(forest.definitions().count(), 2);
+    assert_eq!(forest.scripts().count(), 4);
+
+    // sources, and trees, removed from the forest aren't found, and don't shift the others.
+    let removed = forest.source.sources.remove(0).origin.unwrap();
+    forest.tree.trees.remove(0);
+
+    assert!(forest.get(&removed).is_none());
+    assert!(!forest.contains(&removed));
+    for origin in forest
+        .source
+        .sources
+        .iter()
+        .map(|source| source.origin.clone().unwrap())
+        .collect::<Vec<_>>()
+    {
+        let (source, tree) = forest.get(&origin).unwrap();
+
+        assert_eq!(source.origin.as_deref(), Some(origin.as_str()));
+        assert_eq!(tree.source, origin);
+    }
+
+    forest.tree.trees.clear();
+    assert!(forest.get("src/Foo/Bar/bar.ara").is_none());
+}
@@ -0,0 +1,137 @@
+#![cfg(feature = "remote")]
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+use tiny_http::Method;
+use tiny_http::Response;
+use tiny_http::Server;
+
+use ara_forest::cache::Cache;
+use ara_forest::config::CachePolicy;
+use ara_forest::config::Config;
+use ara_forest::remote::HttpRemoteCache;
+use ara_forest::Parser;
+
+mod common;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+type Entries = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+// an in-memory stand-in for a remote cache server.
+fn serve() -> (String, Entries) {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+    let entries: Entries = Arc::default();
+
+    let store = entries.clone();
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let key = request.url().trim_start_matches('/').to_string();
+            let response = match request.method() {
+                Method::Get => match store.lock().unwrap().get(&key) {
+                    Some(data) => Response::from_data(data.clone()),
+                    None => Response::from_data(vec![]).with_status_code(404),
+                },
+                Method::Put => {
+                    let mut data = Vec::new();
+                    request.as_reader().read_to_end(&mut data).unwrap();
+                    store.lock().unwrap().insert(key, data);
+
+                    Response::from_data(vec![]).with_status_code(204)
+                }
+                _ => Response::from_data(vec![]).with_status_code(405),
+            };
+
+            request.respond(response).unwrap();
+        }
+    });
+
+    (url, entries)
+}
+
+fn project_a(cache: &str, url: &str) -> Config {
+    common::project_a(cache).with_remote_cache(HttpRemoteCache::new(url))
+}
+
+#[test]
+fn test_remote_cache_is_written_and_read_back() {
+    let (url, entries) = serve();
+
+    let config = project_a("remote-a", &url);
+    Cache::new(&config).clear().ok();
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    assert_eq!(entries.lock().unwrap().len(), 6);
+
+    // a machine with an empty local cache loads the trees from the remote cache,
+    // and keeps a local copy.
+    let config = project_a("remote-b", &url);
+    let cache = Cache::new(&config);
+    cache.clear().ok();
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+
+    assert_eq!(forest.tree.trees.len(), 6);
+    assert_eq!(cache.stats().unwrap().entries, 6);
+    assert_eq!(entries.lock().unwrap().len(), 6);
+}
+
+#[test]
+fn test_unreachable_remote_cache() {
+    let config = project_a("remote-unreachable", "http://127.0.0.1:9");
+    fs::remove_dir_all(config.cache.as_ref().unwrap()).ok();
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+    assert_eq!(forest.tree.trees.len(), 6);
+
+    let config = project_a("remote-unreachable-strict", "http://127.0.0.1:9")
+        .with_cache_policy(CachePolicy::Strict);
+    fs::remove_dir_all(config.cache.as_ref().unwrap()).ok();
+
+    let report = Parser::new(&config)
+        .unwrap()
+        .parse()
+        .expect_err("Expected an error Report, but got a Forest object");
+
+    assert!(report
+        .issues
+        .iter()
+        .all(|issue| issue.code.as_deref() == Some("F0014")));
+}
+
+#[test]
+fn test_remote_cache_without_a_cache_directory() {
+    let (url, entries) = serve();
+
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+    let config = Config::new(root)
+        .with_source("src")
+        .with_definitions(vec![
+            format!("vendor/std-bar/definitions"),
+            format!("vendor/std-foo/definitions"),
+        ])
+        .with_remote_cache(HttpRemoteCache::new(&url));
+
+    Parser::new(&config).unwrap().parse().unwrap();
+    assert_eq!(entries.lock().unwrap().len(), 6);
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+    assert_eq!(forest.tree.trees.len(), 6);
+
+    // the same sources under another root hit the entries cached for their old origins.
+    let moved = Config::new(format!("{MANIFEST_DIR}/tests/examples/project-a/src"))
+        .with_source(".")
+        .with_remote_cache(HttpRemoteCache::new(&url));
+
+    let forest = Parser::new(&moved).unwrap().parse().unwrap();
+    assert_eq!(entries.lock().unwrap().len(), 6);
+    for (source, tree) in forest.iter() {
+        assert_eq!(&tree.source, source.origin.as_ref().unwrap());
+    }
+}
@@ -1,8 +1,10 @@
 use std::env;
+use std::path::PathBuf;
 
 use ara_forest::config::Config;
 use ara_forest::error::Error;
 use ara_forest::source::SourceFilesCollector;
+use ara_forest::vendor::VendorDefinitionsCollector;
 
 const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
 
@@ -53,3 +55,38 @@ fn test_trying_to_collect_files_in_a_invalid_path() {
         "Expected an InvalidSource error, but got something else",
     );
 }
+
+#[test]
+fn test_collecting_vendor_definitions_in_project_a() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+    let config = Config::new(&root)
+        .with_source("src")
+        .with_vendor_directory("vendor");
+
+    let definitions = VendorDefinitionsCollector::new(&config).collect().unwrap();
+
+    assert_eq!(
+        definitions,
+        vec![
+            PathBuf::from("vendor/std-bar/definitions"),
+            PathBuf::from("vendor/std-foo/definitions"),
+        ]
+    );
+
+    let files = SourceFilesCollector::new(&config).collect().unwrap();
+
+    assert_eq!(files.len(), 6);
+}
+
+#[test]
+fn test_collecting_files_with_both_listed_and_vendor_definitions() {
+    let root = format!("{MANIFEST_DIR}/tests/examples/project-a");
+    let config = Config::new(&root)
+        .with_source("src")
+        .with_definitions(vec!["vendor/std-bar/definitions"])
+        .with_vendor_directory("vendor");
+
+    let files = SourceFilesCollector::new(&config).collect().unwrap();
+
+    assert_eq!(files.len(), 6);
+}
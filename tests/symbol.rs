@@ -0,0 +1,166 @@
+use std::env;
+use std::fs;
+
+use ara_forest::cache::Cache;
+use ara_forest::config::Config;
+use ara_forest::symbol::SymbolKind;
+use ara_forest::Parser;
+
+mod common;
+
+fn project_a(cache: &str) -> Config {
+    common::project_a(cache).with_symbol_index(true)
+}
+
+#[test]
+fn test_indexing_the_symbols_of_project_a() {
+    let config = project_a("symbols");
+    Cache::new(&config).clear().ok();
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+    let symbols = forest.symbols().unwrap();
+
+    let function = &symbols.get("Example\\list_directories")[0];
+    let (source, _) = forest.get("src/foo.ara").unwrap();
+
+    assert_eq!(function.kind, SymbolKind::Function);
+    assert_eq!(function.origin, "src/foo.ara");
+    assert!(source.content[function.span.0..].starts_with("function list_directories"));
+
+    // the leading backslash is optional.
+    assert_eq!(
+        symbols.get("\\Example\\list_directories"),
+        std::slice::from_ref(function)
+    );
+
+    let kinds = ["FOO", "helloWorld", "foo", "hello_world"].map(|name| symbols.get(name)[0].kind);
+    assert_eq!(
+        kinds,
+        [
+            SymbolKind::Class,
+            SymbolKind::Function,
+            SymbolKind::Constant,
+            SymbolKind::TypeAlias,
+        ]
+    );
+
+    assert_eq!(symbols.get("Closure")[0].kind, SymbolKind::Class);
+    assert!(symbols.get("Missing").is_empty());
+
+    // the symbols of each source are cached next to its tree.
+    let cached = || {
+        fs::read_dir(config.cache.as_ref().unwrap())
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(".ara.symbols")
+            })
+            .count()
+    };
+
+    assert_eq!(cached(), 6);
+
+    Cache::new(&config).clear().unwrap();
+
+    assert_eq!(cached(), 0);
+}
+
+#[test]
+fn test_symbols_are_not_indexed_by_default() {
+    let config = project_a("symbols-disabled").with_symbol_index(false);
+
+    let forest = Parser::new(&config).unwrap().parse().unwrap();
+
+    assert!(forest.symbols().is_none());
+}
+
+#[test]
+fn test_indexing_the_symbols_of_a_lazy_forest_from_the_cache() {
+    let config = project_a("symbols-lazy");
+    Cache::new(&config).clear().ok();
+
+    let parser = Parser::new(&config).unwrap();
+    let forest = parser.parse().unwrap();
+    let lazy = parser.parse_lazy().unwrap();
+
+    let symbols = lazy.symbols().unwrap();
+
+    assert_eq!(symbols.len(), forest.symbols().unwrap().len());
+    assert!(lazy
+        .entries()
+        .iter()
+        .all(|entry| !lazy.is_loaded(&entry.origin)));
+}
+
+#[test]
+fn test_stale_cached_symbols_are_rewritten() {
+    let root = env::temp_dir().join("ara-forest-stale-symbols");
+    fs::remove_dir_all(&root).ok();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/foo.ara"), "function foo(): void {}").unwrap();
+
+    let config = Config::new(root.to_string_lossy())
+        .with_source("src")
+        .with_cache_directory(".cache")
+        .with_symbol_index(true);
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    // the symbols are cached under the origin of the source, whatever its content.
+    fs::write(root.join("src/foo.ara"), "function bar(): void {}").unwrap();
+    Parser::new(&config).unwrap().parse().unwrap();
+
+    let lazy = Parser::new(&config).unwrap().parse_lazy().unwrap();
+    let symbols = lazy.symbols().unwrap();
+
+    assert_eq!(symbols.get("bar")[0].origin, "src/foo.ara");
+    assert!(symbols.get("foo").is_empty());
+    assert!(!lazy.is_loaded("src/foo.ara"));
+}
+
+#[test]
+fn test_warming_the_cache_caches_the_symbols() {
+    let config = project_a("symbols-warm");
+    Cache::new(&config).clear().ok();
+
+    Parser::new(&config).unwrap().warm_cache().unwrap();
+
+    let lazy = Parser::new(&config).unwrap().parse_lazy().unwrap();
+    let symbols = lazy.symbols().unwrap();
+
+    assert_eq!(
+        symbols.get("Example\\list_directories")[0].origin,
+        "src/foo.ara"
+    );
+    assert!(lazy
+        .entries()
+        .iter()
+        .all(|entry| !lazy.is_loaded(&entry.origin)));
+}
+
+#[test]
+fn test_iterating_over_the_trees_caches_the_symbols() {
+    let config = project_a("symbols-iter");
+    Cache::new(&config).clear().ok();
+
+    let count = Parser::new(&config)
+        .unwrap()
+        .parse_iter(|trees| trees.count())
+        .unwrap();
+    assert_eq!(count, 6);
+
+    let lazy = Parser::new(&config).unwrap().parse_lazy().unwrap();
+    let symbols = lazy.symbols().unwrap();
+
+    assert_eq!(
+        symbols.get("Example\\list_directories")[0].origin,
+        "src/foo.ara"
+    );
+    assert!(lazy
+        .entries()
+        .iter()
+        .all(|entry| !lazy.is_loaded(&entry.origin)));
+}